@@ -2,7 +2,7 @@ mod handlers;
 mod models;
 
 use crate::handlers::{create_user, delete_user, get_user, list_users, update_user};
-use actix_web::{web, App, HttpServer, Responder};
+use actix_web::{web, App, HttpServer};
 use sqlx::sqlite::SqlitePoolOptions;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -1,5 +1,3 @@
-#![feature(impl_trait_in_assoc_type)]
-
 use std::io;
 use tosic_http::prelude::Method;
 use tosic_http::server::builder::HttpServerBuilder;
@@ -6,6 +6,7 @@ use thiserror::Error;
 use tokio::io;
 use tosic_http::body::BoxBody;
 use tosic_http::error::response_error::ResponseError;
+use tosic_http::error::ServerError;
 use tosic_http::extractors::json::Json;
 use tosic_http::extractors::path::Path as HttpPath;
 use tosic_http::extractors::query::Query;
@@ -22,6 +23,8 @@ enum HttpServerError {
     Tracing(#[from] SetGlobalDefaultError),
     #[error(transparent)]
     Io(#[from] io::Error),
+    #[error(transparent)]
+    Server(#[from] ServerError),
 }
 
 impl ResponseError for HttpServerError {}
@@ -8,7 +8,7 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 use thiserror::Error;
 use tokio::io;
-use tosic_http::error::Error;
+use tosic_http::error::{Error, ServerError};
 use tosic_http::prelude::{
     get, CompressionLayer, HttpPayload, HttpRequest, HttpResponse, HttpServer,
 };
@@ -82,6 +82,8 @@ enum HttpServerError {
     Tracing(#[from] SetGlobalDefaultError),
     #[error(transparent)]
     Io(#[from] io::Error),
+    #[error(transparent)]
+    Server(#[from] ServerError),
 }
 
 #[derive(Debug, Serialize, Deserialize, Dummy)]
@@ -3,6 +3,7 @@
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::io;
+use tosic_http::error::ServerError;
 use tosic_http::prelude::HttpResponse;
 use tosic_http::server::builder::HttpServerBuilder;
 use tosic_http_macro::get;
@@ -65,6 +66,8 @@ enum HttpServerError {
     Tracing(#[from] SetGlobalDefaultError),
     #[error(transparent)]
     Io(#[from] io::Error),
+    #[error(transparent)]
+    Server(#[from] ServerError),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,13 @@
+//! Errors for route patterns that can not be parsed
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid route `{route}`: {reason}")]
+/// A route pattern that can not be registered, like a parameter with an invalid constraint
+pub struct InvalidRoute {
+    /// The pattern of the route
+    pub route: String,
+    /// Why the pattern is invalid
+    pub reason: String,
+}
@@ -12,10 +12,16 @@ use std::fmt::Debug;
 use thiserror::Error;
 
 mod foreign_impls;
+pub mod invalid_route;
 pub(crate) mod macros;
 pub mod response_error;
+pub mod route_conflict;
+pub mod url_generation;
 
+pub use invalid_route::InvalidRoute;
 pub use response_error::ResponseError;
+pub use route_conflict::{ConflictKind, RouteConflict, RouteConflicts};
+pub use url_generation::UrlGenerationError;
 
 #[derive(Debug, Error)]
 #[allow(missing_docs)]
@@ -51,6 +57,16 @@ pub enum ServerError {
     InvalidEncoding,
     #[error("Failed to construct the service")]
     ServiceConstructionFailed,
+    #[error("The server has no listeners to accept connections on")]
+    NoListeners,
+    #[error("A startup hook failed: {0}")]
+    Startup(crate::server::lifecycle::BoxError),
+    #[error("A state factory failed: {0}")]
+    StateFactory(crate::server::lifecycle::BoxError),
+    #[error("{0}")]
+    RouteConflicts(RouteConflicts),
+    #[error(transparent)]
+    InvalidRoute(#[from] InvalidRoute),
 }
 
 /// External Error type should implement the `ResponseError` trait.
@@ -89,7 +105,7 @@ impl fmt::Display for Error {
 
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", &self.cause)
+        write!(f, "{:?}", self.cause)
     }
 }
 
@@ -57,6 +57,9 @@ impl ResponseError for ServerError {
 
 impl ResponseError for ExtractionError {
     fn status_code(&self) -> StatusCode {
-        StatusCode::BAD_REQUEST
+        match self {
+            ExtractionError::RequestDataNotFound(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
     }
 }
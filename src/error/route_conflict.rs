@@ -0,0 +1,69 @@
+//! Errors for routes that can not be registered together
+
+use http::Method;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Why two routes conflict
+pub enum ConflictKind {
+    /// The routes match the same requests but name their parameters differently,
+    /// like `/users/{id}` and `/users/{name}`
+    Ambiguous,
+    /// The same route was registered more than once for the same method with the same guards,
+    /// so only the handler registered first could ever be called
+    Duplicate,
+    /// The routes have the same name, so the name does not identify a single route
+    DuplicateName,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Two routes registered for the same method that can not be told apart
+pub struct RouteConflict {
+    /// The method both routes are registered for, for [`ConflictKind::DuplicateName`] the method of the conflicting route
+    pub method: Method,
+    /// The route that was registered first
+    pub existing: String,
+    /// The route that conflicts with it
+    pub conflicting: String,
+    /// Why the routes conflict
+    pub kind: ConflictKind,
+}
+
+impl Display for RouteConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let reason = match self.kind {
+            ConflictKind::Ambiguous => "is ambiguous with",
+            ConflictKind::Duplicate => "is already registered as",
+            // named routes can have different methods so only the pattern is shown
+            ConflictKind::DuplicateName => {
+                return write!(
+                    f,
+                    "{} {} has the same name as {}",
+                    self.method, self.conflicting, self.existing
+                );
+            }
+        };
+
+        write!(
+            f,
+            "{} {} {} {} {}",
+            self.method, self.conflicting, reason, self.method, self.existing
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Every conflict found when the server was built
+pub struct RouteConflicts(pub Vec<RouteConflict>);
+
+impl Display for RouteConflicts {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} conflicting route(s):", self.0.len())?;
+
+        for conflict in &self.0 {
+            write!(f, "\n  {}", conflict)?;
+        }
+
+        Ok(())
+    }
+}
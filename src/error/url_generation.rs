@@ -0,0 +1,38 @@
+//! Errors for building the URL of a named route
+
+use crate::error::{InvalidRoute, ResponseError};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+/// Why [`HttpRequest::url_for`](crate::request::HttpRequest::url_for) could not build a URL
+pub enum UrlGenerationError {
+    /// No route was registered with the name
+    #[error("there is no route named `{0}`")]
+    UnknownRoute(String),
+    /// The route has a parameter that no value was given for
+    #[error("missing a value for the parameter `{0}`")]
+    MissingParameter(String),
+    /// A value was given for a parameter the route does not have
+    #[error("the route has no parameter `{0}`")]
+    UnexpectedParameter(String),
+    /// The value does not match the constraint of the parameter
+    #[error("the value {value:?} does not match the constraint `{constraint}` of the parameter `{name}`")]
+    InvalidParameter {
+        /// The name of the parameter
+        name: String,
+        /// The value that was given
+        value: String,
+        /// The constraint of the parameter
+        constraint: String,
+    },
+    /// The route has a wildcard (`*`), which has no name to give it a value with
+    #[error(
+        "the route has a wildcard that can not be given a value, use a named parameter instead"
+    )]
+    Wildcard,
+    /// The pattern of the route is invalid
+    #[error(transparent)]
+    InvalidRoute(#[from] InvalidRoute),
+}
+
+impl ResponseError for UrlGenerationError {}
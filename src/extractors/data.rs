@@ -10,9 +10,9 @@ use std::sync::Arc;
 
 #[derive(Clone)]
 /// The `Data` extractor
-pub struct Data<T: Send + Sync + 'static>(pub Arc<T>);
+pub struct Data<T: ?Sized + Send + Sync + 'static>(pub Arc<T>);
 
-impl<T: Send + Sync + 'static> Data<T> {
+impl<T: ?Sized + Send + Sync + 'static> Data<T> {
     #[inline]
     /// Creates a new `Data`
     pub(crate) fn new(data: Arc<T>) -> Self {
@@ -25,7 +25,7 @@ impl<T: Send + Sync + 'static> Data<T> {
     }
 }
 
-impl<T: Send + Sync + 'static> FromRequest for Data<T> {
+impl<T: ?Sized + Send + Sync + 'static> FromRequest for Data<T> {
     type Error = ExtractionError;
     type Future = Ready<Result<Data<T>, Self::Error>>;
 
@@ -40,7 +40,7 @@ impl<T: Send + Sync + 'static> FromRequest for Data<T> {
     }
 }
 
-impl<T: Send + Sync + 'static> Deref for Data<T> {
+impl<T: ?Sized + Send + Sync + 'static> Deref for Data<T> {
     type Target = T;
 
     #[inline]
@@ -49,7 +49,7 @@ impl<T: Send + Sync + 'static> Deref for Data<T> {
     }
 }
 
-impl<T: Send + Sync + 'static> Debug for Data<T> {
+impl<T: ?Sized + Send + Sync + 'static> Debug for Data<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Data").finish()
     }
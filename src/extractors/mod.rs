@@ -14,11 +14,15 @@ pub mod data;
 pub mod json;
 pub mod path;
 pub mod query;
+pub mod req_data;
+pub mod snapshot;
 
 pub use data::Data;
 pub use json::Json;
 pub use path::Path;
 pub use query::Query;
+pub use req_data::ReqData;
+pub use snapshot::Snapshot;
 
 #[derive(Debug, Error)]
 #[allow(missing_docs)]
@@ -32,12 +36,14 @@ pub enum ExtractionError {
     DataNotFound,
     #[error(transparent)]
     QuerySerialize(#[from] serde_urlencoded::ser::Error),
-    #[error("An error happened when getting the path parameter: {0}")]
+    #[error("Failed to extract path parameters: {0}")]
     Path(String),
     #[error("Expected there to be param but there was none")]
     MissingPathField,
     #[error("Invalid length of data when extracting")]
     InvalidLength,
+    #[error("No value of type `{0}` was attached to the request, make sure a middleware inserts it before the handler runs")]
+    RequestDataNotFound(&'static str),
 }
 
 impl<E> FromRequest for Option<E>
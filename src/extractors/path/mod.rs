@@ -0,0 +1,94 @@
+//! Extracts data from the path
+
+mod de;
+
+use crate::extractors::ExtractionError;
+use crate::futures::{err, ok, Ready};
+use crate::request::{HttpPayload, HttpRequest};
+use crate::traits::from_request::FromRequest;
+pub(crate) use de::PathDeserializer;
+use serde::de::DeserializeOwned;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+/// Path extractor
+///
+/// Deserializes the parameters of the matched route with serde:
+///
+/// - a single value like `Path<u64>` needs the route to have exactly one parameter
+/// - a tuple like `Path<(u64, String)>` gets the parameters in the order they appear in the route
+/// - a struct deriving `Deserialize` gets the parameters by name
+///
+/// If a value can not be parsed the request is rejected with `400 Bad Request` and the error names the parameter.
+///
+/// # Examples
+/// ```
+/// # use tosic_http::prelude::{HttpServer, Method, Path};
+/// # use serde::Deserialize;
+/// #[derive(Deserialize)]
+/// struct PostPath {
+///     user_id: u64,
+///     post_id: u64,
+/// }
+///
+/// async fn user(id: Path<u64>) -> String {
+///     id.to_string()
+/// }
+///
+/// async fn post(path: Path<(u64, u64)>) -> String {
+///     let (user_id, post_id) = path.into_inner();
+///     format!("{user_id}/{post_id}")
+/// }
+///
+/// async fn post_by_name(path: Path<PostPath>) -> String {
+///     format!("{}/{}", path.user_id, path.post_id)
+/// }
+///
+/// let builder = HttpServer::builder()
+///     .service_method(Method::GET, "/users/{id}", user)
+///     .service_method(Method::GET, "/users/{user_id}/posts/{post_id}", post)
+///     .service_method(Method::GET, "/named/{user_id}/posts/{post_id}", post_by_name)
+///     .bind("127.0.0.1:8080");
+/// ```
+pub struct Path<V>(pub V);
+
+impl<T> Path<T> {
+    #[inline]
+    /// Creates a new path extractor
+    pub(crate) fn new(value: T) -> Self {
+        Path(value)
+    }
+
+    #[inline]
+    /// Returns the inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for Path<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> std::ops::DerefMut for Path<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: DeserializeOwned> FromRequest for Path<T> {
+    type Error = ExtractionError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut HttpPayload) -> Self::Future {
+        match T::deserialize(PathDeserializer::new(req.params())) {
+            Ok(value) => ok(Path::new(value)),
+            Err(error) => err(ExtractionError::Path(error.to_string())),
+        }
+    }
+}
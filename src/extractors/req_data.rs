@@ -0,0 +1,56 @@
+//! Extractor for values attached to a single request
+
+use crate::extractors::ExtractionError;
+use crate::futures::{err, ok, Ready};
+use crate::request::{HttpPayload, HttpRequest};
+use crate::traits::from_request::FromRequest;
+use std::fmt::Debug;
+use std::ops::Deref;
+
+#[derive(Clone)]
+/// The `ReqData` extractor
+///
+/// Reads a value that was inserted into the [`HttpRequest::extensions_mut`] of the request,
+/// usually by a middleware. Unlike [`Data`](crate::extractors::Data) the value only lives as long as the request.
+///
+/// If no value of type `T` was inserted the request fails with `500 Internal Server Error`
+/// since this is a problem with how the server is set up and not with the request.
+pub struct ReqData<T: Clone + Send + Sync + 'static>(pub T);
+
+impl<T: Clone + Send + Sync + 'static> ReqData<T> {
+    #[inline]
+    /// Returns the inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> FromRequest for ReqData<T> {
+    type Error = ExtractionError;
+    type Future = Ready<Result<ReqData<T>, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut HttpPayload) -> Self::Future {
+        match req.extensions().get::<T>() {
+            Some(value) => ok(ReqData(value.clone())),
+            None => err(ExtractionError::RequestDataNotFound(
+                std::any::type_name::<T>(),
+            )),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> Deref for ReqData<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Clone + Send + Sync + Debug + 'static> Debug for ReqData<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ReqData").field(&self.0).finish()
+    }
+}
@@ -0,0 +1,53 @@
+//! Extractor for reloadable state
+
+use crate::extractors::ExtractionError;
+use crate::futures::{err, ok, Ready};
+use crate::request::{HttpPayload, HttpRequest};
+use crate::state::reloadable::Reloadable;
+use crate::traits::from_request::FromRequest;
+use std::fmt::Debug;
+use std::ops::Deref;
+use std::sync::Arc;
+
+#[derive(Clone)]
+/// The `Snapshot` extractor
+///
+/// Reads the current value of a [`Reloadable`] registered as app state.
+/// The value is taken once when the request is extracted so it stays the same for the whole request.
+pub struct Snapshot<T: Send + Sync + 'static>(pub Arc<T>);
+
+impl<T: Send + Sync + 'static> Snapshot<T> {
+    #[inline]
+    /// Returns the inner value
+    pub fn into_inner(self) -> Arc<T> {
+        self.0
+    }
+}
+
+impl<T: Send + Sync + 'static> FromRequest for Snapshot<T> {
+    type Error = ExtractionError;
+    type Future = Ready<Result<Snapshot<T>, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut HttpPayload) -> Self::Future {
+        match req.data.get::<Reloadable<T>>() {
+            Some(reloadable) => ok(Snapshot(reloadable.load())),
+            None => err(ExtractionError::DataNotFound),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Deref for Snapshot<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Send + Sync + 'static> Debug for Snapshot<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Snapshot").finish()
+    }
+}
@@ -0,0 +1,159 @@
+//! Guards decide which of several handlers registered for the same route and method handles a request.
+//!
+//! Guarded handlers are tried in the order they were registered and the first one where every guard
+//! passes handles the request. A handler without guards is used as the fallback when no guarded handler
+//! matches. If there is no fallback the route does not match, and the routes with a lower priority that
+//! match the path are tried next, like `/users/{id}` for a guarded `/users/me`. Only if none of them
+//! match the request is answered with `404 Not Found`.
+//!
+//! Registering a route twice with equal guards is reported as a duplicate. Guards are equal if they are
+//! the same instance, or if both return the same [`Guard::key`].
+//!
+//! # Examples
+//! ```
+//! # use tosic_http::prelude::{HttpServer, Responder, HttpResponse, BoxBody};
+//! # use tosic_http::guard::Header;
+//! # use tosic_http::resource::get;
+//! async fn v1() -> impl Responder<Body = BoxBody> {
+//!     HttpResponse::new(200)
+//! }
+//!
+//! async fn v2() -> impl Responder<Body = BoxBody> {
+//!     HttpResponse::new(200)
+//! }
+//!
+//! let builder = HttpServer::builder()
+//!     .route(get("/users", v2).guard(Header::new("api-version", "2")))
+//!     .route(get("/users", v1))
+//!     .bind("127.0.0.1:8080");
+//! ```
+
+use crate::request::HttpRequest;
+use http::header::{CONTENT_TYPE, HOST};
+use http::{HeaderName, HeaderValue};
+
+/// Decides if a handler should handle a request
+pub trait Guard: Send + Sync + 'static {
+    /// Returns `true` if the request passes the guard
+    fn check(&self, req: &HttpRequest) -> bool;
+
+    /// Identifies what the guard checks, two guards with the same key pass the same requests.
+    ///
+    /// Used to report routes that were registered twice with equal guards. By default a guard has no key
+    /// and is only equal to itself.
+    fn key(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<F> Guard for F
+where
+    F: Fn(&HttpRequest) -> bool + Send + Sync + 'static,
+{
+    fn check(&self, req: &HttpRequest) -> bool {
+        self(req)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Passes requests where the `Host` header matches, used for virtual hosts.
+///
+/// The comparison ignores case, and the port is only compared if the guard includes one.
+pub struct Host(String);
+
+impl Host {
+    /// Creates a guard for `host`, like `example.com`, `example.com:8080` or `[::1]:8080`
+    pub fn new(host: impl Into<String>) -> Self {
+        Self(host.into())
+    }
+}
+
+impl Guard for Host {
+    fn check(&self, req: &HttpRequest) -> bool {
+        let Some(host) = req.headers().get(HOST).and_then(|host| host.to_str().ok()) else {
+            return false;
+        };
+
+        let (host, port) = split_port(host);
+        let (expected_host, expected_port) = split_port(&self.0);
+
+        host.eq_ignore_ascii_case(expected_host)
+            && expected_port.is_none_or(|expected| port == Some(expected))
+    }
+
+    fn key(&self) -> Option<String> {
+        Some(format!("host {}", self.0.to_ascii_lowercase()))
+    }
+}
+
+/// Splits a host like `example.com:8080` or `[::1]:8080` into the host and the port.
+///
+/// The brackets of an IPv6 address are removed, an IPv6 address without brackets has no port.
+fn split_port(authority: &str) -> (&str, Option<&str>) {
+    if let Some(address) = authority.strip_prefix('[') {
+        return match address.split_once(']') {
+            Some((host, port)) => (host, port.strip_prefix(':')),
+            None => (authority, None),
+        };
+    }
+
+    match authority.split_once(':') {
+        Some((host, port)) if !port.contains(':') => (host, Some(port)),
+        _ => (authority, None),
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Passes requests where a header has the given value
+pub struct Header(HeaderName, HeaderValue);
+
+impl Header {
+    /// Creates a guard for the header `name` with `value`
+    ///
+    /// # Panics
+    /// Panics if `name` or `value` is not a valid header name or value.
+    pub fn new(name: &str, value: &str) -> Self {
+        Self(
+            HeaderName::try_from(name).expect("invalid header name"),
+            HeaderValue::try_from(value).expect("invalid header value"),
+        )
+    }
+}
+
+impl Guard for Header {
+    fn check(&self, req: &HttpRequest) -> bool {
+        req.headers()
+            .get_all(&self.0)
+            .iter()
+            .any(|value| value == self.1)
+    }
+
+    fn key(&self) -> Option<String> {
+        Some(format!("header {}: {:?}", self.0, self.1))
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Passes requests where the `Content-Type` header has the given media type, parameters like `charset` are ignored
+pub struct ContentType(mime::Mime);
+
+impl ContentType {
+    /// Creates a guard for the media type `mime`
+    pub fn new(mime: mime::Mime) -> Self {
+        Self(mime)
+    }
+}
+
+impl Guard for ContentType {
+    fn check(&self, req: &HttpRequest) -> bool {
+        req.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<mime::Mime>().ok())
+            .is_some_and(|mime| mime.essence_str() == self.0.essence_str())
+    }
+
+    fn key(&self) -> Option<String> {
+        Some(format!("content-type {}", self.0.essence_str()))
+    }
+}
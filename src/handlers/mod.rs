@@ -1,20 +1,30 @@
 //! Stores the handlers for each route. and keyed by method and then stored in a tree.
 
 mod not_found;
+mod redirect;
 pub(crate) mod wrapper;
 
-use crate::error::Error;
+use crate::error::{ConflictKind, Error, InvalidRoute, RouteConflict};
 use crate::handlers::not_found::not_found;
+use crate::handlers::redirect::redirect_trailing_slash;
 use crate::handlers::wrapper::HandlerWrapper;
-use crate::route::{HandlerFn, Route, RouteNode};
+use crate::request::HttpRequest;
+use crate::route::{
+    Endpoint, HandlerFn, Params, PathNormalization, PathSegment, Route, RouteMatch, RouteNode,
+};
 use crate::traits::from_request::FromRequest;
 use crate::traits::handler::Handler;
 use http::Method;
-use std::collections::{BTreeMap, HashMap};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::future::Future;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 use tracing::debug;
 
+/// The pattern of every named route keyed by its name
+pub(crate) type NamedRoutes = HashMap<Arc<str>, Route>;
+
 #[derive(Default, Debug, Clone)]
 /// A collection of handlers for each route.
 pub struct Handlers(pub HashMap<Method, RouteNode>);
@@ -33,37 +43,186 @@ impl Handlers {
         Error: From<Args::Error>,
     {
         let entry = self.entry(method).or_default();
-        let route = Route::new(path);
-        entry.insert(&route, handler);
+
+        match Route::parse(path) {
+            Ok(route) => entry.insert(&route, handler),
+            Err(err) => entry.add_invalid(err),
+        }
+    }
+
+    /// Insert a handler and its guards for a route and method
+    ///
+    /// A route that can not be parsed is recorded and reported by [`Handlers::invalid_routes`].
+    pub(crate) fn insert_endpoint(&mut self, method: Method, path: &str, endpoint: Endpoint) {
+        let entry = self.entry(method).or_default();
+
+        match Route::parse(path) {
+            Ok(route) => entry.insert_endpoint(&route, endpoint),
+            Err(err) => entry.add_invalid(err),
+        }
     }
 
-    #[cfg_attr(feature = "trace", tracing::instrument(level = "trace", skip(self)))]
-    /// Get the handler for a given method and path
-    pub fn get_handler(&self, method: &Method, path: &str) -> HandlerWrapper {
-        let entry = self.get(method);
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(level = "trace", skip(self, req))
+    )]
+    /// Get the handler for the method and path of `req`, the path is normalized with `normalization` first
+    pub fn get_handler(
+        &self,
+        req: &HttpRequest,
+        normalization: &PathNormalization,
+    ) -> HandlerWrapper {
+        let (method, path) = (req.method(), req.uri().path());
+
+        let Some(node) = self.get(method) else {
+            debug!("No handler found for any {} method", method);
+            return Self::not_found();
+        };
+
+        let (route, trailing_slash) = normalization.normalize(path);
 
-        if let Some(node) = entry {
-            let route = Route::new(path);
-            let handler = node.match_path(&route);
+        // routes declared with a trailing slash end with an empty segment, which the normalized route
+        // only has if trailing slashes are kept, otherwise they are tried with one too
+        let slashed = (!normalization.keeps_trailing_slash()).then(|| with_trailing_slash(&route));
+        let match_slashed = || {
+            slashed
+                .as_ref()
+                .and_then(|slashed| node.match_path(slashed, Some(req)))
+        };
 
-            if let Some(handler) = handler {
+        // the flag is set if the route matched without the trailing slash of the request
+        let found = if trailing_slash {
+            match_slashed().map(|handler| (handler, false)).or_else(|| {
+                node.match_path(&route, Some(req))
+                    .map(|handler| (handler, true))
+            })
+        } else {
+            node.match_path(&route, Some(req))
+                .or_else(match_slashed)
+                .map(|handler| (handler, false))
+        };
+
+        match found {
+            Some(((_, _, route_match), true)) if normalization.redirects_trailing_slash() => {
+                debug!("Redirecting {} {} without the trailing slash", method, path);
+                (redirect_trailing_slash(&route), Params::new(), route_match).into()
+            }
+            Some((handler, _)) => {
                 debug!("Handler found for {} {}", method, path);
                 handler.into()
-            } else {
+            }
+            None => {
                 debug!("No handler found for {} {}", method, path);
-                (Self::not_found_handler(), BTreeMap::new()).into()
+                Self::not_found()
             }
-        } else {
-            debug!("No handler found for any {} method", method);
-            (Self::not_found_handler(), BTreeMap::new()).into()
         }
     }
 
+    /// internal method to get the not found handler for a request that did not match any route
+    fn not_found() -> HandlerWrapper {
+        (
+            Self::not_found_handler(),
+            Params::new(),
+            RouteMatch::NotFound,
+        )
+            .into()
+    }
+
     /// internal method to get the not found handler
-    fn not_found_handler() -> HandlerFn {
+    pub(crate) fn not_found_handler() -> HandlerFn {
         HandlerFn::wrap(not_found)
     }
 
+    /// Returns every route that was registered with a pattern that can not be parsed
+    pub fn invalid_routes(&self) -> Vec<InvalidRoute> {
+        self.values()
+            .flat_map(|node| node.invalid_routes().iter().cloned())
+            .collect()
+    }
+
+    /// Returns every pair of routes that were registered for the same method and can not be told apart
+    pub fn conflicts(&self) -> Vec<RouteConflict> {
+        let mut conflicts = self
+            .iter()
+            .flat_map(|(method, node)| {
+                node.conflicts()
+                    .into_iter()
+                    .map(|(existing, conflicting, kind)| RouteConflict {
+                        method: method.clone(),
+                        existing: existing.to_string(),
+                        conflicting: conflicting.to_string(),
+                        kind,
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let mut named = HashMap::<Arc<str>, Route>::new();
+
+        for (method, name, route) in self.all_named_routes() {
+            match named.get(&name) {
+                Some(existing) if *existing != route => conflicts.push(RouteConflict {
+                    method,
+                    existing: existing.to_string(),
+                    conflicting: route.to_string(),
+                    kind: ConflictKind::DuplicateName,
+                }),
+                Some(_) => {}
+                None => {
+                    named.insert(name, route);
+                }
+            }
+        }
+
+        conflicts.sort_by(|a, b| {
+            (a.method.as_str(), &a.conflicting).cmp(&(b.method.as_str(), &b.conflicting))
+        });
+
+        conflicts
+    }
+
+    /// Returns the pattern of every named route keyed by its name
+    pub(crate) fn named_routes(&self) -> NamedRoutes {
+        let mut named = NamedRoutes::new();
+
+        for (_, name, route) in self.all_named_routes() {
+            named.entry(name).or_insert(route);
+        }
+
+        named
+    }
+
+    /// Returns every named route of every method, sorted by method and then by pattern
+    fn all_named_routes(&self) -> Vec<(Method, Arc<str>, Route)> {
+        let mut routes = self
+            .iter()
+            .flat_map(|(method, node)| {
+                node.named_routes()
+                    .into_iter()
+                    .map(|(name, route)| (method.clone(), name, route))
+            })
+            .collect::<Vec<_>>();
+
+        routes.sort_by_cached_key(|(method, _, route)| (method.to_string(), route.to_string()));
+
+        routes
+    }
+
+    /// Returns every registered method and route pattern, sorted by pattern and then by method
+    pub fn routes(&self) -> Vec<(Method, String)> {
+        let mut routes = self
+            .iter()
+            .flat_map(|(method, node)| {
+                node.routes()
+                    .into_iter()
+                    .map(|route| (method.clone(), route.to_string()))
+            })
+            .collect::<Vec<_>>();
+
+        routes.sort_by(|a, b| (&a.1, a.0.as_str()).cmp(&(&b.1, b.0.as_str())));
+
+        routes
+    }
+
     /// Extends the handlers with a new set of handlers
     pub fn extend(&mut self, other: Handlers) {
         for (method, other_node) in other.0 {
@@ -88,3 +247,13 @@ impl DerefMut for Handlers {
         &mut self.0
     }
 }
+
+/// Returns `route` with an empty last segment, like a route declared with a trailing slash
+fn with_trailing_slash(route: &Route) -> Route {
+    route
+        .segments()
+        .iter()
+        .cloned()
+        .chain([PathSegment::Static(Cow::Borrowed(""))])
+        .collect()
+}
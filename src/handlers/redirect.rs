@@ -0,0 +1,44 @@
+//! Redirects requests to the path without a trailing slash
+
+use crate::body::BoxBody;
+use crate::request::HttpRequest;
+use crate::response::HttpResponse;
+use crate::route::{encode_segment, HandlerFn, PathSegment, Route};
+use crate::traits::responder::Responder;
+use http::header::LOCATION;
+use http::HeaderValue;
+
+/// Creates a handler that redirects to the normalized `route` of the request, keeping its query.
+///
+/// The location is built from the segments of the route instead of the request path, so it has the slashes
+/// and dot segments that normalization removed resolved too. Every segment is encoded again and empty
+/// segments are left out, so the location always starts with exactly one `/` and can't be mistaken for
+/// a URL of another host like `//evil.com`.
+pub(crate) fn redirect_trailing_slash(route: &Route) -> HandlerFn {
+    let segments = route
+        .segments()
+        .iter()
+        .filter_map(|segment| match segment {
+            PathSegment::Static(segment) if !segment.is_empty() => Some(encode_segment(segment)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let path = format!("/{}", segments.join("/"));
+
+    HandlerFn::wrap(move |req: HttpRequest| redirect(path.clone(), req))
+}
+
+async fn redirect(path: String, req: HttpRequest) -> impl Responder<Body = BoxBody> {
+    let location = match req.uri().query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path,
+    };
+
+    let mut response = HttpResponse::new(308);
+
+    if let Ok(location) = HeaderValue::try_from(location) {
+        response.headers_mut().insert(LOCATION, location);
+    }
+
+    response
+}
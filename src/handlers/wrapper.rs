@@ -1,10 +1,13 @@
 //! Wraps a handler and a map of parameters to make it simpler to work with in other parts of the project
 
-use crate::route::HandlerFn;
-use std::collections::BTreeMap;
+use crate::route::{HandlerFn, Params, RouteMatch};
 
-/// Wraps a handler and a map of parameters
-pub struct HandlerWrapper(pub(crate) HandlerFn, pub(crate) BTreeMap<String, String>);
+/// Wraps a handler, its parameters and the route that matched
+pub struct HandlerWrapper(
+    pub(crate) HandlerFn,
+    pub(crate) Params,
+    pub(crate) RouteMatch,
+);
 
 impl HandlerWrapper {
     /// Returns the handler
@@ -13,8 +16,8 @@ impl HandlerWrapper {
     }
 }
 
-impl From<(HandlerFn, BTreeMap<String, String>)> for HandlerWrapper {
-    fn from(value: (HandlerFn, BTreeMap<String, String>)) -> Self {
-        Self(value.0, value.1)
+impl From<(HandlerFn, Params, RouteMatch)> for HandlerWrapper {
+    fn from(value: (HandlerFn, Params, RouteMatch)) -> Self {
+        Self(value.0, value.1, value.2)
     }
 }
@@ -1,7 +1,4 @@
 #![doc = include_str!("../README.md")]
-#![feature(tuple_trait)]
-#![feature(associated_type_defaults)]
-#![feature(fn_traits)]
 #![feature(impl_trait_in_assoc_type)]
 //#![deny(missing_docs)]
 
@@ -12,6 +9,7 @@ pub mod extractors;
 pub mod futures;
 #[cfg(not(feature = "utils"))]
 pub(crate) mod futures;
+pub mod guard;
 pub(crate) mod handlers;
 mod middleware;
 pub mod prelude;
@@ -19,8 +17,9 @@ pub mod request;
 pub mod resource;
 pub mod response;
 pub(crate) mod route;
+pub mod scope;
 pub mod server;
 pub mod services;
-pub(crate) mod state;
+pub mod state;
 pub mod traits;
 pub(crate) mod utils;
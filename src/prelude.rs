@@ -12,6 +12,8 @@ pub use crate::request::*;
 pub use crate::response::*;
 pub use crate::server::HttpServer;
 pub use crate::services::HttpService;
+pub use crate::state::State;
 pub use crate::traits::*;
+pub use http::Extensions;
 pub use http::HeaderMap;
 pub use http::Method;
@@ -2,19 +2,24 @@
 
 mod test;
 
+pub use crate::route::{Params, RouteMatch};
+
 use crate::body::message_body::MessageBody;
 use crate::body::BoxBody;
-use crate::error::ServerError;
+use crate::error::{ServerError, UrlGenerationError};
 use crate::futures::{ok, Ready};
+use crate::handlers::NamedRoutes;
+use crate::server::connection::{Connection, ConnectionMeta};
 use crate::state::State;
 use crate::traits::from_request::FromRequest;
 use bytes::Bytes;
-use http::{HeaderMap, HeaderValue, Method, Uri, Version};
+use http::{Extensions, HeaderMap, HeaderValue, Method, Uri, Version};
 use httparse::{Request, Status};
-use std::collections::BTreeMap;
 use std::convert::Infallible;
+use std::fmt::Display;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
+use std::sync::Arc;
 
 #[derive(Clone, Debug, Default)]
 #[allow(missing_docs)]
@@ -24,8 +29,12 @@ pub struct HttpRequest {
     pub uri: Uri,
     pub headers: HeaderMap,
     pub version: Version,
-    pub params: BTreeMap<String, String>,
+    pub params: Params,
+    pub(crate) route_match: RouteMatch,
+    pub(crate) named_routes: Arc<NamedRoutes>,
     pub data: State,
+    pub(crate) extensions: Extensions,
+    pub(crate) connection: Option<Arc<Connection>>,
 }
 
 #[derive(Clone, Debug)]
@@ -123,14 +132,102 @@ impl HttpRequest {
     }
 
     /// Get the params
-    pub fn params(&self) -> &BTreeMap<String, String> {
+    pub fn params(&self) -> &Params {
         &self.params
     }
 
     /// Get the params
-    pub fn params_mut(&mut self) -> &mut BTreeMap<String, String> {
+    pub fn params_mut(&mut self) -> &mut Params {
         &mut self.params
     }
+
+    /// Get the route that matched this request
+    pub fn route_match(&self) -> &RouteMatch {
+        &self.route_match
+    }
+
+    /// Get the pattern of the route that matched this request, like `/users/{id}`.
+    ///
+    /// Unlike the path of the request the pattern is the same for every request to the route,
+    /// so it can be used to group metrics and logs. It is `None` if no route matched.
+    pub fn match_pattern(&self) -> Option<&str> {
+        self.route_match.pattern()
+    }
+
+    /// Get the name of the route that matched this request, if it was given one
+    pub fn match_name(&self) -> Option<&str> {
+        self.route_match.name()
+    }
+
+    /// Builds the path of the route named `name` with its parameters replaced by `params`.
+    ///
+    /// Values are percent-encoded, so they can contain any character. Every parameter of the route needs
+    /// a value and every value has to belong to a parameter of the route.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tosic_http::prelude::{HttpRequest, HttpResponse, HttpServer};
+    /// # use tosic_http::resource::get;
+    /// # use tosic_http::error::UrlGenerationError;
+    /// async fn user() -> HttpResponse {
+    ///     HttpResponse::new(200)
+    /// }
+    ///
+    /// async fn create_user(req: HttpRequest) -> Result<HttpResponse, UrlGenerationError> {
+    ///     let location = req.url_for("user", [("id", 7)])?;
+    ///
+    ///     let mut response = HttpResponse::new(201);
+    ///     response.headers_mut().insert("location", location.parse().unwrap());
+    ///     Ok(response)
+    /// }
+    ///
+    /// let builder = HttpServer::builder()
+    ///     .route(get("/users/{id}", user).name("user"))
+    ///     .bind("127.0.0.1:8080");
+    /// ```
+    pub fn url_for<K, V>(
+        &self,
+        name: &str,
+        params: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<String, UrlGenerationError>
+    where
+        K: AsRef<str>,
+        V: Display,
+    {
+        let route = self
+            .named_routes
+            .get(name)
+            .ok_or_else(|| UrlGenerationError::UnknownRoute(name.to_string()))?;
+
+        let params = params
+            .into_iter()
+            .map(|(key, value)| (key.as_ref().to_string(), value.to_string()))
+            .collect();
+
+        route.url_for(&params)
+    }
+
+    /// Get the values attached to this request, see [`ReqData`](crate::extractors::ReqData)
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    /// Get the values attached to this request mutably, used by middleware to pass values to handlers
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+
+    /// Get the metadata of the connection the request was received on
+    pub fn connection(&self) -> Option<&ConnectionMeta> {
+        self.connection.as_ref().map(|connection| &connection.meta)
+    }
+
+    /// Get the extensions populated by the `on_connect` hooks for the connection the request was received on
+    pub fn connection_extensions(&self) -> Option<&Extensions> {
+        self.connection
+            .as_ref()
+            .map(|connection| &connection.extensions)
+    }
 }
 
 impl FromRequest for HttpRequest {
@@ -60,3 +60,127 @@ fn test_from_request() {
     assert_eq!(result.uri, "/test".parse::<Uri>().unwrap());
     assert_eq!(result.version, Version::HTTP_11);
 }*/
+
+#[tokio::test]
+async fn test_request_extensions() {
+    use crate::error::ResponseError;
+    use crate::extractors::ReqData;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct UserId(u64);
+
+    let (mut request, mut payload) = HttpRequest::from_bytes(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+
+    let missing = ReqData::<UserId>::from_request(&request, &mut payload).await;
+    assert_eq!(
+        missing.unwrap_err().status_code(),
+        http::StatusCode::INTERNAL_SERVER_ERROR
+    );
+
+    request.extensions_mut().insert(UserId(7));
+    assert_eq!(request.extensions().get::<UserId>(), Some(&UserId(7)));
+
+    let user = ReqData::<UserId>::from_request(&request, &mut payload)
+        .await
+        .unwrap();
+    assert_eq!(*user, UserId(7));
+
+    assert_eq!(request.extensions_mut().remove::<UserId>(), Some(UserId(7)));
+    assert!(request.extensions().get::<UserId>().is_none());
+}
+
+#[tokio::test]
+async fn test_path_extraction() {
+    use crate::extractors::Path;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct PostPath {
+        post_id: u64,
+        user_id: String,
+    }
+
+    let (mut request, mut payload) = HttpRequest::from_bytes(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    request
+        .params_mut()
+        .extend([("user_id".to_string(), "zed".to_string())]);
+
+    let user = Path::<String>::from_request(&request, &mut payload)
+        .await
+        .unwrap();
+    assert_eq!(*user, "zed");
+
+    request
+        .params_mut()
+        .extend([("post_id".to_string(), "7".to_string())]);
+
+    let tuple = Path::<(String, u64)>::from_request(&request, &mut payload)
+        .await
+        .unwrap();
+    assert_eq!(tuple.into_inner(), ("zed".to_string(), 7));
+
+    let named = Path::<PostPath>::from_request(&request, &mut payload)
+        .await
+        .unwrap();
+    assert_eq!(
+        named.into_inner(),
+        PostPath {
+            post_id: 7,
+            user_id: "zed".to_string()
+        }
+    );
+
+    let error = Path::<(u64, u64)>::from_request(&request, &mut payload)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("parameter `user_id`"));
+
+    let error = Path::<u64>::from_request(&request, &mut payload)
+        .await
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("expected 1 parameter but the route has 2"));
+}
+
+#[tokio::test]
+async fn test_typed_path() {
+    use crate::traits::TypedPath;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct FilePath {
+        user: u32,
+        path: String,
+    }
+
+    impl TypedPath for FilePath {
+        const PATH: &'static str = "/users/{user:int}/files/{*path}";
+
+        fn params(&self) -> Vec<(&'static str, String)> {
+            vec![("user", self.user.to_string()), ("path", self.path.clone())]
+        }
+    }
+
+    let (mut request, _) = HttpRequest::from_bytes(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    request.params_mut().extend([
+        ("user".to_string(), "7".to_string()),
+        ("path".to_string(), "a b/c".to_string()),
+    ]);
+
+    let path = FilePath::from_params(request.params()).unwrap();
+    assert_eq!(
+        path,
+        FilePath {
+            user: 7,
+            path: "a b/c".to_string()
+        }
+    );
+    assert_eq!(path.to_url().unwrap(), "/users/7/files/a%20b/c");
+
+    request
+        .params_mut()
+        .insert("user".to_string(), "me".to_string());
+    let error = FilePath::from_params(request.params()).unwrap_err();
+    assert!(error.to_string().contains("parameter `user`"));
+}
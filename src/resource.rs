@@ -2,12 +2,20 @@
 
 use crate::body::BoxBody;
 use crate::error::Error;
+use crate::guard::Guard;
 use crate::handlers::Handlers;
 use crate::prelude::{FromRequest, Handler, Responder};
+use crate::request::{HttpPayload, HttpRequest};
+use crate::response::HttpResponse;
+use crate::route::{Endpoint, HandlerFn, LayerFn};
+use crate::scope::apply_layers;
+use crate::traits::typed_path::TypedPath;
 use http::Method;
 use paste::paste;
 use std::borrow::Cow;
 use std::future::Future;
+use std::sync::Arc;
+use tower::{Layer, Service};
 
 #[allow(unused_imports)]
 use crate::prelude::HttpServer;
@@ -15,7 +23,10 @@ use crate::prelude::HttpServer;
 /// RouteBuilder is a builder pattern for creating routes for an [`HttpServer`].
 pub struct RouteBuilder<'a> {
     path: Cow<'a, str>,
-    handlers: Handlers,
+    handlers: Vec<(Method, HandlerFn)>,
+    layers: Vec<Arc<LayerFn>>,
+    guards: Vec<Arc<dyn Guard>>,
+    name: Option<String>,
 }
 
 macro_rules! route_method (
@@ -65,11 +76,16 @@ impl<'a> RouteBuilder<'a> {
     pub(crate) fn new(path: &'a str) -> Self {
         Self {
             path: path.into(),
-            handlers: Handlers::new(),
+            handlers: Vec::new(),
+            layers: Vec::new(),
+            guards: Vec::new(),
+            name: None,
         }
     }
 
     /// Add a handler for the given method
+    ///
+    /// Adding a second handler for the same method is reported as a conflict when the server is built.
     pub fn insert_handler<H, Args>(mut self, method: Method, handler: H) -> Self
     where
         H: Handler<Args> + Send + Sync + 'static,
@@ -79,21 +95,119 @@ impl<'a> RouteBuilder<'a> {
         H::Output: Responder<Body = BoxBody> + 'static,
         Error: From<Args::Error>,
     {
-        if self.handlers.contains_key(&method) {
-            panic!("You cant have more than one handler per method!")
-        }
+        self.handlers.push((method, HandlerFn::wrap(handler)));
+
+        self
+    }
+
+    /// Wraps every handler of the route in `layer`, inside the layers added to the server.
+    ///
+    /// Layers run in the order they were added, the first layer added sees the request first.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tosic_http::prelude::{HttpServer, Responder, HttpResponse, BoxBody};
+    /// # use tosic_http::resource::get;
+    /// # use tower::util::MapResponseLayer;
+    /// async fn slow() -> impl Responder<Body = BoxBody> {
+    ///     HttpResponse::new(200)
+    /// }
+    ///
+    /// let builder = HttpServer::builder()
+    ///     .route(get("/slow", slow).wrap(MapResponseLayer::new(|response: HttpResponse| response)))
+    ///     .bind("127.0.0.1:8080");
+    /// ```
+    pub fn wrap<S>(mut self, layer: S) -> Self
+    where
+        S: Layer<HandlerFn> + Send + Sync + 'static,
+        S::Service: Service<(HttpRequest, HttpPayload), Response = HttpResponse, Error = Error>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        <S::Service as Service<(HttpRequest, HttpPayload)>>::Future: Send + 'static,
+    {
+        self.layers
+            .push(Arc::new(move |handler: HandlerFn| handler.layer(&layer)));
+
+        self
+    }
+
+    /// Only lets the handlers of the route handle requests that pass `guard`, see [`guard`](crate::guard).
+    pub fn guard(mut self, guard: impl Guard) -> Self {
+        self.guards.push(Arc::new(guard));
+
+        self
+    }
 
-        self.handlers.insert(method, &self.path, handler);
+    /// Names the route so its URL can be built with [`HttpRequest::url_for`]
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
 
         self
     }
 
     /// Get the handlers
     pub(crate) fn handlers(self) -> Handlers {
-        self.handlers
+        let mut handlers = Handlers::new();
+        let (path, routes) = self.into_parts();
+
+        for (method, endpoint) in routes {
+            handlers.insert_endpoint(method, &path, endpoint);
+        }
+
+        handlers
+    }
+
+    /// Get the path and the endpoint for each method with the layers and guards of the route applied
+    pub(crate) fn into_parts(self) -> (Cow<'a, str>, Vec<(Method, Endpoint)>) {
+        let layers = self.layers;
+        let guards = self.guards;
+        let name = self.name;
+        let handlers = self
+            .handlers
+            .into_iter()
+            .map(|(method, handler)| {
+                let endpoint = Endpoint {
+                    handler: apply_layers(&layers, handler),
+                    guards: guards.clone(),
+                    name: None,
+                }
+                .with_name(name.as_deref());
+
+                (method, endpoint)
+            })
+            .collect();
+
+        (self.path, handlers)
     }
 
     route_method! {get, post, put, delete, trace, options, head, connect, patch}
 }
 
 route_function! {get, post, put, delete, trace, options, head, connect, patch}
+
+/// Create a new [`RouteBuilder`] for the pattern of a [`TypedPath`]
+///
+/// # Examples
+/// ```
+/// # use tosic_http::prelude::{HttpServer, TypedPath};
+/// # use tosic_http::resource::typed;
+/// # use serde::Deserialize;
+/// #[derive(TypedPath, Deserialize)]
+/// #[typed_path("/users/{id}")]
+/// struct UserPath {
+///     id: u32,
+/// }
+///
+/// async fn show(path: UserPath) -> String {
+///     path.id.to_string()
+/// }
+///
+/// let builder = HttpServer::builder()
+///     .route(typed::<UserPath>().get(show))
+///     .bind("127.0.0.1:8080");
+/// ```
+pub fn typed<P: TypedPath>() -> RouteBuilder<'static> {
+    RouteBuilder::new(P::PATH)
+}
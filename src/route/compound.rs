@@ -0,0 +1,194 @@
+//! Segments that mix literal text and parameters like `{name}.{ext}` or `v{version}`
+
+use crate::error::UrlGenerationError;
+use crate::route::route::encode_segment;
+use crate::route::Constraint;
+use regex::Regex;
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone)]
+/// # Compound
+///
+/// A segment with one or more parameters mixed with literal text, parameters can have a [`Constraint`]
+/// like `v{version:int}`. Unconstrained parameters match as little as possible, so `{name}.{ext}` matches
+/// `archive.tar.gz` with `name` as `archive` and `ext` as `tar.gz`.
+pub struct Compound {
+    pattern: Cow<'static, str>,
+    regex: Regex,
+    names: Vec<Cow<'static, str>>,
+}
+
+impl Compound {
+    /// Parses a compound segment
+    ///
+    /// # Errors
+    /// Returns why the segment is invalid if a parameter is not closed or has an invalid constraint.
+    pub fn parse(segment: &str) -> Result<Self, String> {
+        let mut source = String::from("^");
+        let mut names = Vec::new();
+        let mut rest = segment;
+
+        while let Some(start) = rest.find('{') {
+            source.push_str(&regex::escape(&rest[..start]));
+
+            let end = start
+                + closing_brace(&rest[start..])
+                    .ok_or_else(|| format!("unclosed parameter in the segment `{}`", segment))?;
+            let parameter = &rest[start + 1..end];
+
+            if parameter.starts_with('*') {
+                return Err(format!(
+                    "a catch-all has to be the whole segment in `{}`",
+                    segment
+                ));
+            }
+
+            let group = match parameter.split_once(':') {
+                Some((name, constraint)) => {
+                    names.push(Cow::Owned(name.to_string()));
+                    parse_constraint(constraint)?.regex_source()
+                }
+                None => {
+                    names.push(Cow::Owned(parameter.to_string()));
+                    "[^/]+?".to_string()
+                }
+            };
+
+            // the group is named after the index of the parameter, constraints can have groups of their own
+            source.push_str(&format!("(?P<__p{}>{})", names.len() - 1, group));
+            rest = &rest[end + 1..];
+        }
+
+        source.push_str(&regex::escape(rest));
+        source.push('$');
+
+        Ok(Self {
+            pattern: Cow::Owned(segment.to_string()),
+            regex: Regex::new(&source)
+                .map_err(|err| format!("invalid segment `{}`: {}", segment, err))?,
+            names,
+        })
+    }
+
+    /// Matches `value` against the segment and returns the value of every parameter
+    pub fn captures(&self, value: &str) -> Option<Vec<(String, String)>> {
+        let captures = self.regex.captures(value)?;
+
+        Some(
+            self.names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| {
+                    let value = captures
+                        .name(&format!("__p{}", index))
+                        .map_or("", |capture| capture.as_str());
+                    (name.to_string(), value.to_string())
+                })
+                .collect(),
+        )
+    }
+
+    /// The names of the parameters in the segment, in the order they appear
+    pub fn names(&self) -> &[Cow<'static, str>] {
+        &self.names
+    }
+}
+
+impl Compound {
+    /// Builds the segment with every parameter replaced by its percent-encoded value, the values are only
+    /// checked against their constraints if `checked` is set
+    pub(crate) fn fill<'a>(
+        &self,
+        value: impl Fn(&str) -> Result<&'a String, UrlGenerationError>,
+        checked: bool,
+    ) -> Result<String, UrlGenerationError> {
+        let mut segment = String::new();
+        let mut rest = &*self.pattern;
+
+        while let Some(start) = rest.find('{') {
+            segment.push_str(&encode_segment(&rest[..start]));
+
+            // the pattern was validated when it was parsed
+            let end = start + closing_brace(&rest[start..]).unwrap_or(rest.len() - start - 1);
+            let parameter = &rest[start + 1..end];
+
+            let (name, constraint) = match parameter.split_once(':') {
+                Some((name, constraint)) if checked => (name, Constraint::parse(constraint).ok()),
+                Some((name, _)) => (name, None),
+                None => (parameter, None),
+            };
+
+            let value = value(name)?;
+
+            if let Some(constraint) = constraint.filter(|constraint| !constraint.is_match(value)) {
+                return Err(UrlGenerationError::InvalidParameter {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                    constraint: constraint.to_string(),
+                });
+            }
+
+            segment.push_str(&encode_segment(value));
+            rest = &rest[end + 1..];
+        }
+
+        segment.push_str(&encode_segment(rest));
+
+        Ok(segment)
+    }
+
+    /// Checks if two compound segments match the same values, regardless of what their parameters are named
+    pub fn same_shape(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+impl PartialEq for Compound {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl Eq for Compound {}
+
+impl Display for Compound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+/// Describes a constraint that is not a valid regex
+pub(crate) fn invalid_constraint(constraint: &str, err: regex::Error) -> String {
+    format!("invalid constraint `{}`: {}", constraint, err)
+}
+
+/// Parses the constraint of a parameter, it can't be empty since `{id:}` would only match an empty value
+pub(crate) fn parse_constraint(constraint: &str) -> Result<Constraint, String> {
+    if constraint.is_empty() {
+        return Err("expected a constraint after `:`".to_string());
+    }
+
+    Constraint::parse(constraint).map_err(|err| invalid_constraint(constraint, err))
+}
+
+/// Finds the index of the brace closing the one `segment` starts with, braces inside a constraint like `\d{3}` are skipped.
+pub(crate) fn closing_brace(segment: &str) -> Option<usize> {
+    let mut depth = 0;
+
+    for (index, char) in segment.char_indices() {
+        match char {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
@@ -0,0 +1,96 @@
+//! Constraints that a path parameter has to satisfy for a route to match
+
+use regex::Regex;
+use std::fmt::{Display, Formatter};
+use std::sync::LazyLock;
+
+/// What [`Constraint::Int`] matches, used both to match whole segments and inside compound segments
+const INT: &str = "-?[0-9]+";
+
+/// [`INT`] anchored to match a whole segment
+static INT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!("^(?:{})$", INT)).expect("the int constraint is a valid regex")
+});
+
+#[derive(Debug, Clone)]
+/// # Constraint
+///
+/// A constraint on a parameter, written after the parameter name like `{id:int}` or `{id:\d+}`
+///
+/// - `int` -> an integer, digits with an optional leading `-`
+/// - `uuid` -> a hyphenated uuid like `67e55044-10b1-426f-9247-bb680e5fe0c8`
+/// - anything else -> a regex that has to match the whole segment
+pub enum Constraint {
+    Int,
+    Uuid,
+    Regex(Regex),
+}
+
+impl Constraint {
+    /// Parses a constraint
+    ///
+    /// # Errors
+    /// Returns the error of the regex if the constraint is not `int`, `uuid` or a valid regex.
+    pub fn parse(constraint: &str) -> Result<Self, regex::Error> {
+        Ok(match constraint {
+            "int" => Constraint::Int,
+            "uuid" => Constraint::Uuid,
+            pattern => Constraint::Regex(Regex::new(&format!("^(?:{})$", pattern))?),
+        })
+    }
+
+    /// The constraint as a regex without anchors, used when the parameter is only part of a segment
+    pub fn regex_source(&self) -> String {
+        match self {
+            Constraint::Int => INT.to_string(),
+            Constraint::Uuid => {
+                "[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}"
+                    .to_string()
+            }
+            Constraint::Regex(_) => format!("(?:{})", self),
+        }
+    }
+
+    /// Checks if `value` satisfies the constraint
+    pub fn is_match(&self, value: &str) -> bool {
+        match self {
+            Constraint::Int => INT_REGEX.is_match(value),
+            Constraint::Uuid => is_uuid(value),
+            Constraint::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+
+impl PartialEq for Constraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+impl Eq for Constraint {}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constraint::Int => write!(f, "int"),
+            Constraint::Uuid => write!(f, "uuid"),
+            Constraint::Regex(regex) => {
+                let pattern = regex.as_str();
+                write!(f, "{}", &pattern[4..pattern.len() - 2])
+            }
+        }
+    }
+}
+
+/// Checks if `value` is a hyphenated uuid
+fn is_uuid(value: &str) -> bool {
+    const GROUPS: [usize; 5] = [8, 4, 4, 4, 12];
+
+    let mut groups = value.split('-');
+
+    GROUPS.iter().all(|len| {
+        groups.next().is_some_and(|group| {
+            group.len() == *len && group.bytes().all(|byte| byte.is_ascii_hexdigit())
+        })
+    }) && groups.next().is_none()
+}
@@ -1,17 +1,24 @@
 //! # Route
 //!
-//! When parsing params we currently use a `BTreeMap`, this is fine until we try extract
-//! the parameter in order since the BTreeMap will keep it sorted and possibly not in the same order
-//! as what the user might think or see in the path. This is a better solution than using the
-//! original solution which was a `HashMap` which failed sometimes, and sometimes it worked based on
-//! the hashing algo
+//! Parameters of a matched route are stored in [`Params`], which keeps them in the order they appear
+//! in the route so they can be extracted positionally with [`Path`](crate::extractors::path::Path).
 
+mod compound;
+mod constraint;
 pub(crate) mod node;
+mod normalize;
+mod params;
 mod path;
+mod route_match;
 
 #[allow(clippy::module_inception)]
 mod route;
 mod test;
 
+pub use compound::Compound;
+pub use constraint::*;
 pub use node::*;
+pub use normalize::{PathNormalization, TrailingSlash};
+pub use params::Params;
 pub use route::*;
+pub use route_match::RouteMatch;
@@ -1,20 +1,21 @@
 //! Routes and handlers are stored in a tree structure.
 
 use crate::body::BoxBody;
-use crate::error::Error;
+use crate::error::{ConflictKind, Error, InvalidRoute};
 use crate::request::{HttpPayload, HttpRequest};
 use crate::response::HttpResponse;
 use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::future::Future;
 use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use tower::Service;
+use tower::{Layer, Service, ServiceExt};
 //use crate::handlers::HandlerType;
-use super::{PathSegment, Route};
+use super::{Compound, Constraint, Params, PathSegment, Route, RouteMatch};
+use crate::guard::Guard;
 use crate::traits::from_request::FromRequest;
 use crate::traits::handler::Handler;
 use crate::traits::responder::Responder;
@@ -33,6 +34,52 @@ impl HandlerFn {
     {
         Self(wrap_handler_fn(Arc::new(handler)))
     }
+
+    /// Wraps the handler in a tower [`Layer`], used for middleware that only applies to a single route
+    pub fn layer<S>(self, layer: S) -> HandlerFn
+    where
+        S: Layer<HandlerFn>,
+        S::Service: Service<(HttpRequest, HttpPayload), Response = HttpResponse, Error = Error>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        <S::Service as Service<(HttpRequest, HttpPayload)>>::Future: Send + 'static,
+    {
+        Self::from_service(layer.layer(self))
+    }
+
+    /// Create a new handler function from a closure
+    pub(crate) fn from_fn<F>(f: F) -> HandlerFn
+    where
+        F: Fn(
+                HttpRequest,
+                &mut HttpPayload,
+            ) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    /// Create a new handler function from a [`Service`], usually a handler wrapped in one or more layers
+    pub(crate) fn from_service<S>(service: S) -> HandlerFn
+    where
+        S: Service<(HttpRequest, HttpPayload), Response = HttpResponse, Error = Error>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Future: Send + 'static,
+    {
+        Self::from_fn(move |req, payload| {
+            let service = service.clone();
+            let payload = payload.clone();
+
+            Box::pin(service.oneshot((req, payload)))
+        })
+    }
 }
 
 impl Deref for HandlerFn {
@@ -51,6 +98,9 @@ impl DerefMut for HandlerFn {
     }
 }
 
+/// Wraps a [`HandlerFn`] in a layer, used to store layers until the handlers they apply to are known.
+pub(crate) type LayerFn = dyn Fn(HandlerFn) -> HandlerFn + Send + Sync;
+
 /// The inner type of [`HandlerFn`].
 pub(crate) type HandlerInner = dyn Fn(
         HttpRequest,
@@ -105,6 +155,49 @@ where
     ) as Arc<HandlerInner>
 }
 
+#[derive(Clone)]
+/// A handler together with the guards that have to pass for it to handle a request.
+pub(crate) struct Endpoint {
+    pub(crate) handler: HandlerFn,
+    pub(crate) guards: Vec<Arc<dyn Guard>>,
+    pub(crate) name: Option<Arc<str>>,
+}
+
+impl From<HandlerFn> for Endpoint {
+    fn from(handler: HandlerFn) -> Self {
+        Self {
+            handler,
+            guards: Vec::new(),
+            name: None,
+        }
+    }
+}
+
+impl Endpoint {
+    /// Returns `true` if both endpoints have equal guards, in which case only the first one can ever handle a request.
+    ///
+    /// Guards are equal if they are the same instance or have the same [`Guard::key`].
+    fn same_guards(&self, other: &Endpoint) -> bool {
+        self.guards.len() == other.guards.len()
+            && self
+                .guards
+                .iter()
+                .zip(&other.guards)
+                .all(|(a, b)| Arc::ptr_eq(a, b) || a.key().is_some_and(|key| b.key() == Some(key)))
+    }
+
+    /// Gives the endpoint a name that its route can be looked up by
+    pub(crate) fn with_name(mut self, name: Option<&str>) -> Self {
+        self.name = name.map(Arc::from);
+        self
+    }
+
+    /// Checks every guard of the endpoint against `req`
+    fn check(&self, req: &HttpRequest) -> bool {
+        self.guards.iter().all(|guard| guard.check(req))
+    }
+}
+
 #[derive(Clone)]
 /// A tree of routes.
 ///
@@ -115,34 +208,55 @@ where
 /// - `/` -> static route
 /// - `/a/b` -> static route
 /// - `/{param}/a` -> parameter as the first segment
+/// - `/a/{id:int}` -> parameter with a [`Constraint`](super::Constraint)
+/// - `/files/{name}.{ext}` -> [`Compound`](super::Compound) segment mixing text and parameters
 /// - `/a/*/b` -> wildcard in the middle
-/// - `/a/**` -> deep wildcard
+/// - `/a/**` -> deep wildcard, the rest of the path is stored under `wildcard_deep`
+/// - `/files/{*path}` -> named catch-all, the rest of the path is stored under `path`
+///
+/// ## Priority
+///
+/// At every segment the children are tried in this order, and if a child does not lead to a handler
+/// the next one is tried. A route whose endpoints are all guarded only leads to a handler if the guards
+/// of one of them pass:
+///
+/// 1. static segments
+/// 2. constrained parameters, in the order they were registered
+/// 3. compound segments, in the order they were registered
+/// 4. parameters
+/// 5. wildcards
+/// 6. deep wildcards and catch-alls
+///
+/// Parameters are matched by position, the names come from the route that was matched. Two routes
+/// that only differ in the names of their parameters can not be told apart, and registering the same
+/// route twice with the same guards would leave one of the handlers unreachable. Both are reported by
+/// [`RouteNode::conflicts`] instead of replacing the handler that was registered first.
 pub struct RouteNode {
     static_children: HashMap<Cow<'static, str>, RouteNode>,
-    parameter_child: Option<(Cow<'static, str>, Box<RouteNode>)>,
+    constrained_children: Vec<(Constraint, RouteNode)>,
+    compound_children: Vec<(Compound, RouteNode)>,
+    parameter_child: Option<Box<RouteNode>>,
     wildcard_child: Option<Box<RouteNode>>,
-    handler: Option<HandlerFn>,
+    catch_all_child: Option<Box<RouteNode>>,
+    route: Option<Route>,
+    pattern: Option<Arc<str>>,
+    conflicts: Vec<(Route, Route, ConflictKind)>,
+    invalid: Vec<InvalidRoute>,
+    endpoints: Vec<Endpoint>,
 }
 
 impl Debug for RouteNode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        #[derive(Debug)]
-        enum DebugHandler {
-            Some,
-            None,
-        }
-
         let mut binding = f.debug_struct("RouteNode");
         let f = binding
             .field("static_children", &self.static_children)
+            .field("constrained_children", &self.constrained_children)
+            .field("compound_children", &self.compound_children)
             .field("parameter_child", &self.parameter_child)
-            .field("wildcard_child", &self.wildcard_child);
-
-        let f = if self.handler.is_some() {
-            f.field("handler", &DebugHandler::Some)
-        } else {
-            f.field("handler", &DebugHandler::None)
-        };
+            .field("wildcard_child", &self.wildcard_child)
+            .field("catch_all_child", &self.catch_all_child)
+            .field("route", &self.route)
+            .field("endpoints", &self.endpoints.len());
 
         f.finish()
     }
@@ -159,9 +273,16 @@ impl RouteNode {
     pub fn new() -> Self {
         RouteNode {
             static_children: HashMap::new(),
+            constrained_children: Vec::new(),
+            compound_children: Vec::new(),
             parameter_child: None,
             wildcard_child: None,
-            handler: None,
+            catch_all_child: None,
+            route: None,
+            pattern: None,
+            conflicts: Vec::new(),
+            invalid: Vec::new(),
+            endpoints: Vec::new(),
         }
     }
 
@@ -175,24 +296,34 @@ impl RouteNode {
             }
         }
 
-        if let Some((other_param_name, other_child)) = other.parameter_child {
-            if let Some((_param_name, child)) = &mut self.parameter_child {
-                child.extend(*other_child);
-            } else {
-                self.parameter_child = Some((other_param_name, other_child));
-            }
+        for (constraint, other_child) in other.constrained_children {
+            self.constrained_child(constraint).extend(other_child);
         }
 
-        if let Some(other_child) = other.wildcard_child {
-            if let Some(child) = &mut self.wildcard_child {
-                child.extend(*other_child);
-            } else {
-                self.wildcard_child = Some(other_child);
+        for (compound, other_child) in other.compound_children {
+            self.compound_child(compound).extend(other_child);
+        }
+
+        for (child, other_child) in [
+            (&mut self.parameter_child, other.parameter_child),
+            (&mut self.wildcard_child, other.wildcard_child),
+            (&mut self.catch_all_child, other.catch_all_child),
+        ] {
+            if let Some(other_child) = other_child {
+                match child {
+                    Some(child) => child.extend(*other_child),
+                    None => *child = Some(other_child),
+                }
             }
         }
 
-        if other.handler.is_some() {
-            self.handler = other.handler;
+        self.conflicts.extend(other.conflicts);
+        self.invalid.extend(other.invalid);
+
+        if let Some(route) = other.route {
+            for endpoint in other.endpoints {
+                self.add_endpoint(&route, endpoint);
+            }
         }
     }
 
@@ -203,105 +334,343 @@ impl RouteNode {
         Args::Future: Future + Send + 'static,
         Error: From<Args::Error>,
     {
-        let handler_fn = HandlerFn::wrap(handler);
+        self.insert_handler_fn(route, HandlerFn::wrap(handler));
+    }
+
+    /// Inserts an already wrapped handler into the route node.
+    pub(crate) fn insert_handler_fn(&mut self, route: &Route, handler: HandlerFn) {
+        self.insert_endpoint(route, handler.into());
+    }
 
-        self.insert_segments(route.segments(), handler_fn);
+    /// Inserts a handler and its guards into the route node.
+    pub(crate) fn insert_endpoint(&mut self, route: &Route, endpoint: Endpoint) {
+        self.insert_segments(route, route.segments(), endpoint);
+    }
+
+    /// Adds an endpoint for `route` to this node.
+    ///
+    /// The endpoint is recorded as a conflict instead if it can never handle a request because of an
+    /// endpoint that was added before.
+    fn add_endpoint(&mut self, route: &Route, endpoint: Endpoint) {
+        let existing = match &self.route {
+            Some(existing) if existing.param_names() != route.param_names() => {
+                Some((existing.clone(), ConflictKind::Ambiguous))
+            }
+            Some(existing)
+                if self
+                    .endpoints
+                    .iter()
+                    .any(|other| other.same_guards(&endpoint)) =>
+            {
+                Some((existing.clone(), ConflictKind::Duplicate))
+            }
+            Some(_) => None,
+            None => {
+                self.route = Some(route.clone());
+                self.pattern = Some(route.to_string().into());
+                None
+            }
+        };
+
+        if let Some((existing, kind)) = existing {
+            self.conflicts.push((existing, route.clone(), kind));
+            return;
+        }
+
+        self.endpoints.push(endpoint);
     }
 
     /// Inserts individual segments into the route node.
-    fn insert_segments(&mut self, segments: &[PathSegment], handler: HandlerFn) {
+    fn insert_segments(&mut self, route: &Route, segments: &[PathSegment], handler: Endpoint) {
         if segments.is_empty() {
-            self.handler = Some(handler);
+            self.add_endpoint(route, handler);
             return;
         }
 
-        match &segments[0] {
+        let child = match &segments[0] {
             PathSegment::Static(segment) => {
-                let child = self.static_children.entry(segment.clone()).or_default();
-                child.insert_segments(&segments[1..], handler);
+                self.static_children.entry(segment.clone()).or_default()
             }
-            PathSegment::Parameter(param_name) => {
-                if self.parameter_child.is_none() {
-                    self.parameter_child = Some((param_name.clone(), Box::new(RouteNode::new())));
-                }
-
-                let (_, child_node) = self.parameter_child.as_mut().unwrap();
-                child_node.insert_segments(&segments[1..], handler);
+            PathSegment::Constrained(_, constraint) => self.constrained_child(constraint.clone()),
+            PathSegment::Compound(compound) => self.compound_child(compound.clone()),
+            PathSegment::Parameter(_) => self.parameter_child.get_or_insert_default(),
+            PathSegment::Wildcard => self.wildcard_child.get_or_insert_default(),
+            PathSegment::WildcardDeep | PathSegment::CatchAll(_) => {
+                // a catch-all handles all remaining segments, routes are only parsed if it is the last one
+                self.catch_all_child
+                    .get_or_insert_default()
+                    .add_endpoint(route, handler);
+                return;
             }
-            PathSegment::Wildcard => {
-                if self.wildcard_child.is_none() {
-                    self.wildcard_child = Some(Box::new(RouteNode::new()));
-                }
+        };
 
-                let child_node = self.wildcard_child.as_mut().unwrap();
-                child_node.insert_segments(&segments[1..], handler);
-            }
-            PathSegment::WildcardDeep => {
-                if self.wildcard_child.is_none() {
-                    self.wildcard_child = Some(Box::new(RouteNode::new()));
-                }
+        child.insert_segments(route, &segments[1..], handler);
+    }
+
+    /// Gets the child for a constrained parameter, creating it if there is none with the same constraint yet.
+    fn constrained_child(&mut self, constraint: Constraint) -> &mut RouteNode {
+        let position = self
+            .constrained_children
+            .iter()
+            .position(|(existing, _)| *existing == constraint);
 
-                let child_node = self.wildcard_child.as_mut().unwrap();
-                // WildcardDeep means this node should handle all remaining segments
-                child_node.handler = Some(handler.clone());
+        let index = position.unwrap_or_else(|| {
+            self.constrained_children
+                .push((constraint, RouteNode::new()));
+            self.constrained_children.len() - 1
+        });
+
+        &mut self.constrained_children[index].1
+    }
+
+    /// Gets the child for a compound segment, creating it if there is none matching the same values yet.
+    fn compound_child(&mut self, compound: Compound) -> &mut RouteNode {
+        let position = self
+            .compound_children
+            .iter()
+            .position(|(existing, _)| existing.same_shape(&compound));
+
+        let index = position.unwrap_or_else(|| {
+            self.compound_children.push((compound, RouteNode::new()));
+            self.compound_children.len() - 1
+        });
+
+        &mut self.compound_children[index].1
+    }
+
+    /// Records a route that could not be inserted because its pattern is invalid
+    pub(crate) fn add_invalid(&mut self, invalid: InvalidRoute) {
+        self.invalid.push(invalid);
+    }
+
+    /// Returns the routes that could not be inserted into the tree because their pattern is invalid
+    pub fn invalid_routes(&self) -> &[InvalidRoute] {
+        &self.invalid
+    }
+
+    /// Returns every pair of routes in the tree that conflict with each other,
+    /// the route that was registered first comes first.
+    pub fn conflicts(&self) -> Vec<(Route, Route, ConflictKind)> {
+        let mut conflicts = self.conflicts.clone();
+
+        for child in self.children() {
+            conflicts.extend(child.conflicts());
+        }
+
+        conflicts
+    }
+
+    /// Returns the pattern of every route in the tree that has a handler
+    pub fn routes(&self) -> Vec<Route> {
+        let mut routes = Vec::new();
+
+        if let (Some(route), false) = (&self.route, self.endpoints.is_empty()) {
+            routes.push(route.clone());
+        }
+
+        for child in self.children() {
+            routes.extend(child.routes());
+        }
+
+        routes
+    }
+
+    /// Returns the name and pattern of every named route in the tree
+    pub fn named_routes(&self) -> Vec<(Arc<str>, Route)> {
+        let mut routes = Vec::new();
+
+        if let Some(route) = &self.route {
+            for name in self
+                .endpoints
+                .iter()
+                .filter_map(|endpoint| endpoint.name.as_ref())
+            {
+                routes.push((name.clone(), route.clone()));
             }
         }
+
+        for child in self.children() {
+            routes.extend(child.named_routes());
+        }
+
+        routes
+    }
+
+    /// Iterates over the direct children of the node.
+    fn children(&self) -> impl Iterator<Item = &RouteNode> {
+        self.static_children
+            .values()
+            .chain(self.constrained_children.iter().map(|(_, child)| child))
+            .chain(self.compound_children.iter().map(|(_, child)| child))
+            .chain(self.parameter_child.as_deref())
+            .chain(self.wildcard_child.as_deref())
+            .chain(self.catch_all_child.as_deref())
     }
 
-    /// Matches a path against the route node.
-    pub fn match_path(&self, route: &Route) -> Option<(HandlerFn, BTreeMap<String, String>)> {
-        self.match_segments(route.segments())
+    /// Matches a path against the route node, returning the handler, the parameters and the route that matched.
+    ///
+    /// With a request only routes with an endpoint whose guards pass for `req` match, so a route whose guards
+    /// fail falls through to routes with a lower priority. Without one the guards are ignored.
+    pub fn match_path(
+        &self,
+        route: &Route,
+        req: Option<&HttpRequest>,
+    ) -> Option<(HandlerFn, Params, RouteMatch)> {
+        self.match_segments(route.segments(), req)
     }
 
-    /// Matches segments against the route node.
+    /// Matches segments against the route node, checking guards against `req` if there is one.
+    ///
+    /// The segments are expected to be static, like the segments of [`Route::from_request_path`].
     pub fn match_segments(
         &self,
         segments: &[PathSegment],
-    ) -> Option<(HandlerFn, BTreeMap<String, String>)> {
-        if segments.is_empty() {
-            return self
-                .handler
-                .clone()
-                .map(|handler| (handler, BTreeMap::new()));
+        req: Option<&HttpRequest>,
+    ) -> Option<(HandlerFn, Params, RouteMatch)> {
+        let segments = segments
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Static(segment) => Cow::Borrowed(&**segment),
+                segment => Cow::Owned(segment.to_string()),
+            })
+            .collect::<Vec<_>>();
+
+        let mut values = Vec::new();
+        let (node, endpoint) = self.find(&segments, req, &mut values)?;
+
+        let names = node.route.as_ref()?.param_names();
+        let params = names
+            .into_iter()
+            .map(|name| name.into_owned())
+            .zip(values)
+            .collect();
+
+        let route_match = RouteMatch::Matched {
+            pattern: node.pattern.clone()?,
+            name: endpoint.name.clone(),
+        };
+
+        Some((endpoint.handler.clone(), params, route_match))
+    }
+
+    /// Returns the first endpoint whose guards pass for `req`, guarded endpoints are tried
+    /// in the order they were added and the endpoint without guards is tried last.
+    ///
+    /// Without a request the guards are ignored and the first endpoint is returned.
+    fn select(&self, req: Option<&HttpRequest>) -> Option<&Endpoint> {
+        let guarded = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| !endpoint.guards.is_empty());
+        let fallback = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.guards.is_empty());
+
+        guarded
+            .chain(fallback)
+            .find(|endpoint| req.is_none_or(|req| endpoint.check(req)))
+    }
+
+    /// Finds the node with the handler for `segments`, the value of every parameter is pushed to `values`.
+    ///
+    /// Children are tried in priority order and the next child is tried whenever one does not lead to a handler
+    /// for `req`.
+    fn find<'a>(
+        &'a self,
+        segments: &[Cow<'_, str>],
+        req: Option<&HttpRequest>,
+        values: &mut Vec<String>,
+    ) -> Option<(&'a RouteNode, &'a Endpoint)> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return self.select(req).map(|endpoint| (self, endpoint));
+        };
+
+        if let Some(child) = self.static_children.get(&**segment) {
+            if let Some(found) = child.find(rest, req, values) {
+                return Some(found);
+            }
+        }
+
+        if !segment.is_empty() {
+            if let Some(found) = self.find_dynamic(segment, rest, req, values) {
+                return Some(found);
+            }
         }
 
-        if let PathSegment::Static(segment) = &segments[0] {
-            if let Some(child) = self.static_children.get(segment) {
-                if let Some((handler, params)) = child.match_segments(&segments[1..]) {
-                    return Some((handler, params));
+        if let Some(child) = &self.catch_all_child {
+            if segments.iter().any(|segment| !segment.is_empty()) {
+                if let Some(endpoint) = child.select(req) {
+                    values.push(join_segments(segments));
+                    return Some((child, endpoint));
                 }
             }
         }
 
-        if let Some((param_name, child)) = &self.parameter_child {
-            if let Some((handler, mut params)) = child.match_segments(&segments[1..]) {
-                if let PathSegment::Static(value) = &segments[0] {
-                    params.insert(param_name.to_string(), value.to_string());
+        None
+    }
+
+    /// Tries the children that capture the segment, parameters never match an empty segment.
+    fn find_dynamic<'a>(
+        &'a self,
+        segment: &str,
+        rest: &[Cow<'_, str>],
+        req: Option<&HttpRequest>,
+        values: &mut Vec<String>,
+    ) -> Option<(&'a RouteNode, &'a Endpoint)> {
+        let len = values.len();
+
+        for (constraint, child) in &self.constrained_children {
+            if constraint.is_match(segment) {
+                values.push(segment.to_string());
+
+                if let Some(node) = child.find(rest, req, values) {
+                    return Some(node);
                 }
-                return Some((handler, params));
+
+                values.truncate(len);
             }
         }
 
+        for (compound, child) in &self.compound_children {
+            if let Some(captures) = compound.captures(segment) {
+                values.extend(captures.into_iter().map(|(_, value)| value));
+
+                if let Some(node) = child.find(rest, req, values) {
+                    return Some(node);
+                }
+
+                values.truncate(len);
+            }
+        }
+
+        if let Some(child) = &self.parameter_child {
+            values.push(segment.to_string());
+
+            if let Some(node) = child.find(rest, req, values) {
+                return Some(node);
+            }
+
+            values.truncate(len);
+        }
+
         if let Some(child) = &self.wildcard_child {
-            return if let Some((handler, params)) = child.match_segments(&segments[1..]) {
-                Some((handler, params))
-            } else if let Some(_handler) = &child.handler {
-                let remaining: Vec<_> = segments.to_vec();
-                let mut params = BTreeMap::new();
-                params.insert(
-                    "wildcard_deep".to_string(),
-                    remaining
-                        .iter()
-                        .map(|segment| format!("{}", segment))
-                        .collect::<Vec<_>>()
-                        .join("/"),
-                );
-                Some((child.handler.clone()?, params))
-            } else {
-                None
-            };
+            if let Some(node) = child.find(rest, req, values) {
+                return Some(node);
+            }
         }
 
         None
     }
 }
+
+/// Joins the segments matched by a catch-all, a slash that was decoded inside a segment is encoded again
+/// so it can't be mistaken for a separator. A decoded `%` is encoded first, so `%2F` in the value always
+/// stands for a slash and `%25` for a percent sign.
+fn join_segments(segments: &[Cow<'_, str>]) -> String {
+    segments
+        .iter()
+        .map(|segment| segment.replace('%', "%25").replace('/', "%2F"))
+        .collect::<Vec<_>>()
+        .join("/")
+}
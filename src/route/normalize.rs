@@ -0,0 +1,136 @@
+//! Normalization of request paths before they are matched against the routes
+
+use super::{PathSegment, Route};
+use percent_encoding::percent_decode_str;
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// How a trailing slash on the request path is handled
+pub enum TrailingSlash {
+    /// `/users/` is matched as if it was `/users`, a route declared as `/users/` matches both
+    #[default]
+    Ignore,
+    /// `/users/` is redirected to `/users` with `308 Permanent Redirect` if `/users` has a route,
+    /// a route declared as `/users/` matches both without a redirect
+    Redirect,
+    /// `/users/` is only matched by routes declared with a trailing slash like `/users/` or that end with
+    /// a catch-all, and `/users` is not matched by them
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How the path of a request is normalized before it is matched against the routes.
+///
+/// The path is split into segments before they are percent-decoded, so an encoded slash (`%2F`) stays
+/// inside its segment and is part of the parameter value. A catch-all joins the segments it matched with
+/// `/` and keeps an encoded slash inside a segment as `%2F`, so the value has the segments of the request.
+/// A percent sign in a segment is encoded as `%25` for the same reason.
+/// Dot segments are resolved after decoding, so `%2e%2e` is resolved like `..`.
+///
+/// # Examples
+/// ```
+/// # use tosic_http::prelude::HttpServer;
+/// # use tosic_http::server::{PathNormalization, TrailingSlash};
+/// let builder = HttpServer::builder()
+///     .path_normalization(PathNormalization::new().trailing_slash(TrailingSlash::Redirect))
+///     .bind("127.0.0.1:8080");
+/// ```
+pub struct PathNormalization {
+    merge_slashes: bool,
+    resolve_dot_segments: bool,
+    trailing_slash: TrailingSlash,
+}
+
+impl Default for PathNormalization {
+    fn default() -> Self {
+        Self {
+            merge_slashes: true,
+            resolve_dot_segments: true,
+            trailing_slash: TrailingSlash::default(),
+        }
+    }
+}
+
+impl PathNormalization {
+    /// Creates the default policy, it merges slashes, resolves dot segments and ignores trailing slashes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets if repeated slashes like in `/a//b` are treated as a single slash.
+    ///
+    /// If disabled the empty segment has to match the route, which only a catch-all does.
+    pub fn merge_slashes(mut self, merge_slashes: bool) -> Self {
+        self.merge_slashes = merge_slashes;
+        self
+    }
+
+    /// Sets if `.` and `..` segments are resolved, so `/a/./b/../c` is matched as `/a/c`
+    pub fn resolve_dot_segments(mut self, resolve_dot_segments: bool) -> Self {
+        self.resolve_dot_segments = resolve_dot_segments;
+        self
+    }
+
+    /// Sets how a trailing slash is handled
+    pub fn trailing_slash(mut self, trailing_slash: TrailingSlash) -> Self {
+        self.trailing_slash = trailing_slash;
+        self
+    }
+
+    /// Returns `true` if a request with a trailing slash should be redirected to the path without it
+    pub(crate) fn redirects_trailing_slash(&self) -> bool {
+        self.trailing_slash == TrailingSlash::Redirect
+    }
+
+    /// Returns `true` if a trailing slash is part of the normalized route, so routes with and without one
+    /// never match the same path
+    pub(crate) fn keeps_trailing_slash(&self) -> bool {
+        self.trailing_slash == TrailingSlash::Strict
+    }
+
+    /// Normalizes `path` into a route of decoded static segments.
+    ///
+    /// Also returns `true` if the path had a trailing slash, which is only part of the route with
+    /// [`TrailingSlash::Strict`].
+    pub(crate) fn normalize(&self, path: &str) -> (Route, bool) {
+        let trailing_slash = path.len() > 1 && path.ends_with('/');
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let path = if trailing_slash {
+            &path[..path.len() - 1]
+        } else {
+            path
+        };
+
+        let mut segments: Vec<Cow<'static, str>> = Vec::new();
+
+        for segment in path.split('/') {
+            let segment = decode(segment);
+
+            match &*segment {
+                "" if self.merge_slashes => {}
+                "." if self.resolve_dot_segments => {}
+                ".." if self.resolve_dot_segments => {
+                    segments.pop();
+                }
+                _ => segments.push(segment),
+            }
+        }
+
+        if path.is_empty() && !self.merge_slashes {
+            segments.clear();
+        }
+
+        if trailing_slash && self.trailing_slash == TrailingSlash::Strict {
+            segments.push(Cow::Borrowed(""));
+        }
+
+        let route = segments.into_iter().map(PathSegment::Static).collect();
+
+        (route, trailing_slash)
+    }
+}
+
+/// Percent-decodes a single segment, invalid UTF-8 is replaced with `U+FFFD`
+fn decode(segment: &str) -> Cow<'static, str> {
+    Cow::Owned(percent_decode_str(segment).decode_utf8_lossy().into_owned())
+}
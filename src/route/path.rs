@@ -1,6 +1,7 @@
 //! No idea why this is a struct, but it is and it works
 
-use super::PathSegment;
+use super::compound::{closing_brace, parse_constraint};
+use super::{Compound, PathSegment, Route};
 use std::borrow::Cow;
 
 /// Path parser
@@ -8,20 +9,95 @@ pub struct Path;
 
 impl Path {
     /// Parse a path
-    pub fn parse(path: &str) -> Vec<PathSegment> {
-        path.split('/')
+    ///
+    /// # Errors
+    /// Returns why the path is invalid, like a parameter with a constraint that is not a valid regex or two
+    /// parameters with the same name. These are the checks the service macros do at compile time.
+    pub fn parse(path: &str) -> Result<Vec<PathSegment>, String> {
+        let mut segments = path
+            .split('/')
             .filter(|segment| !segment.is_empty())
             .map(|segment| {
-                if segment.starts_with('{') && segment.ends_with('}') {
-                    PathSegment::Parameter(Cow::Owned(segment[1..segment.len() - 1].to_string()))
-                } else if segment == "*" {
+                Ok(if segment == "*" {
                     PathSegment::Wildcard
                 } else if segment == "**" {
                     PathSegment::WildcardDeep
+                } else if segment.starts_with('{')
+                    && closing_brace(segment) == Some(segment.len() - 1)
+                {
+                    let parameter = &segment[1..segment.len() - 1];
+
+                    if let Some(name) = parameter.strip_prefix('*') {
+                        if name.contains(':') {
+                            return Err(format!(
+                                "the catch-all `{}` can't have a constraint",
+                                segment
+                            ));
+                        }
+
+                        return Ok(PathSegment::CatchAll(Cow::Owned(name.to_string())));
+                    }
+
+                    match parameter.split_once(':') {
+                        Some((name, constraint)) => PathSegment::Constrained(
+                            Cow::Owned(name.to_string()),
+                            parse_constraint(constraint)?,
+                        ),
+                        None => PathSegment::Parameter(Cow::Owned(parameter.to_string())),
+                    }
+                } else if segment.contains('{') {
+                    PathSegment::Compound(Compound::parse(segment)?)
+                } else if segment.contains('}') {
+                    return Err(format!("unmatched `}}` in the segment `{}`", segment));
                 } else {
                     PathSegment::Static(Cow::Owned(segment.to_string()))
-                }
+                })
             })
-            .collect()
+            .collect::<Result<Vec<_>, String>>()?;
+
+        // a deep wildcard or catch-all takes the rest of the path, a segment after it could never match
+        if let Some(position) = segments.iter().position(|segment| {
+            matches!(
+                segment,
+                PathSegment::WildcardDeep | PathSegment::CatchAll(_)
+            )
+        }) {
+            if position != segments.len() - 1 {
+                return Err(format!(
+                    "`{}` matches the rest of the path, it has to be the last segment",
+                    segments[position]
+                ));
+            }
+        }
+
+        // parameters are looked up by name, so every parameter needs a name of its own
+        let mut names = Vec::new();
+
+        for name in segments.iter().cloned().collect::<Route>().param_names() {
+            if name.is_empty() {
+                return Err("parameter without a name, parameters look like `{name}`".to_string());
+            }
+
+            if names.contains(&name) {
+                return Err(format!("duplicate parameter `{}`", name));
+            }
+
+            names.push(name);
+        }
+
+        // a trailing slash is kept as an empty segment, like the path of a request with
+        // `TrailingSlash::Strict`, unless the rest of the path is taken anyway
+        if path.ends_with('/')
+            && segments.last().is_some_and(|segment| {
+                !matches!(
+                    segment,
+                    PathSegment::WildcardDeep | PathSegment::CatchAll(_)
+                )
+            })
+        {
+            segments.push(PathSegment::Static(Cow::Borrowed("")));
+        }
+
+        Ok(segments)
     }
 }
@@ -2,13 +2,39 @@
 //!
 //! A structured representation of a route
 
+use crate::error::{InvalidRoute, UrlGenerationError};
 use crate::route::path::Path;
+use crate::route::{Compound, Constraint, Params, PathNormalization};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::ops::Add;
 
+/// Characters that are percent-encoded in a path segment, `/` is included so a value never adds a segment
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Percent-encodes `value` to be used as a single path segment
+pub(crate) fn encode_segment(value: &str) -> String {
+    utf8_percent_encode(value, SEGMENT).to_string()
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 /// # Route
 ///
@@ -26,32 +52,63 @@ pub struct Route {
 /// A path segment can be:
 /// - a static string
 /// - a parameter
+/// - a parameter with a [`Constraint`]
+/// - a [`Compound`] of literal text and parameters
 /// - a wildcard
 /// - a wildcard deep
+/// - a named catch-all
 pub enum PathSegment {
     Static(Cow<'static, str>),
     Parameter(Cow<'static, str>),
+    Constrained(Cow<'static, str>, Constraint),
+    Compound(Compound),
     Wildcard,
     WildcardDeep,
+    CatchAll(Cow<'static, str>),
 }
 
 impl Display for PathSegment {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Static(string) => write!(f, "{}", string),
-            Self::Parameter(param) => write!(f, "{}", param),
+            Self::Parameter(param) => write!(f, "{{{}}}", param),
+            Self::Constrained(param, constraint) => write!(f, "{{{}:{}}}", param, constraint),
+            Self::Compound(compound) => write!(f, "{}", compound),
             Self::Wildcard => write!(f, "*"),
             Self::WildcardDeep => write!(f, "**"),
+            Self::CatchAll(param) => write!(f, "{{*{}}}", param),
         }
     }
 }
 
 impl Route {
     /// Create a new route
+    ///
+    /// # Panics
+    /// Panics if the route is invalid, use [`Route::parse`] for routes that were not validated yet.
     pub fn new(path: &str) -> Self {
-        Self {
-            path: Path::parse(path),
-        }
+        Self::parse(path).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Parses a route
+    ///
+    /// # Errors
+    /// Returns [`InvalidRoute`] if the route can not be parsed, like a parameter with a constraint
+    /// that is not a valid regex.
+    pub fn parse(path: &str) -> Result<Self, InvalidRoute> {
+        Ok(Self {
+            path: Path::parse(path).map_err(|reason| InvalidRoute {
+                route: path.to_string(),
+                reason,
+            })?,
+        })
+    }
+
+    /// Create a route from the path of a request, every segment is treated as static text.
+    ///
+    /// The path is normalized with the default [`PathNormalization`].
+    pub fn from_request_path(path: &str) -> Self {
+        PathNormalization::default().normalize(path).0
     }
 
     /// Get the segments of the route
@@ -59,13 +116,98 @@ impl Route {
         &self.path
     }
 
+    /// Get the names of the parameters in the route, in the order they appear
+    ///
+    /// A deep wildcard is named `wildcard_deep`, wildcards are not captured and have no name.
+    pub fn param_names(&self) -> Vec<Cow<'static, str>> {
+        self.path
+            .iter()
+            .flat_map(|segment| match segment {
+                PathSegment::Parameter(name)
+                | PathSegment::Constrained(name, _)
+                | PathSegment::CatchAll(name) => vec![name.clone()],
+                PathSegment::Compound(compound) => compound.names().to_vec(),
+                PathSegment::WildcardDeep => vec![Cow::Borrowed("wildcard_deep")],
+                PathSegment::Static(_) | PathSegment::Wildcard => Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Builds the path of the route with the parameters replaced by the values in `params`.
+    ///
+    /// Values are percent-encoded, only the value of a catch-all can contain `/` to span several segments.
+    /// Every parameter of the route needs a value and every value has to belong to a parameter.
+    pub fn url_for(&self, params: &Params) -> Result<String, UrlGenerationError> {
+        self.fill(params, true)
+    }
+
+    /// Builds the path of the route like [`Route::url_for`] without checking the values against the
+    /// constraints of their parameters.
+    pub fn url_for_unchecked(&self, params: &Params) -> Result<String, UrlGenerationError> {
+        self.fill(params, false)
+    }
+
+    /// Builds the path of the route, the values are only checked against their constraints if `checked` is set
+    fn fill(&self, params: &Params, checked: bool) -> Result<String, UrlGenerationError> {
+        let value = |name: &str| {
+            params
+                .get(name)
+                .ok_or_else(|| UrlGenerationError::MissingParameter(name.to_string()))
+        };
+
+        let mut url = String::new();
+
+        for segment in &self.path {
+            url.push('/');
+
+            match segment {
+                PathSegment::Static(text) => url.push_str(&encode_segment(text)),
+                PathSegment::Parameter(name) => url.push_str(&encode_segment(value(name)?)),
+                PathSegment::Constrained(name, constraint) => {
+                    let value = value(name)?;
+
+                    if checked && !constraint.is_match(value) {
+                        return Err(UrlGenerationError::InvalidParameter {
+                            name: name.to_string(),
+                            value: value.to_string(),
+                            constraint: constraint.to_string(),
+                        });
+                    }
+
+                    url.push_str(&encode_segment(value));
+                }
+                PathSegment::Compound(compound) => url.push_str(&compound.fill(value, checked)?),
+                PathSegment::Wildcard => return Err(UrlGenerationError::Wildcard),
+                PathSegment::WildcardDeep => {
+                    url.push_str(&encode_catch_all(value("wildcard_deep")?));
+                }
+                PathSegment::CatchAll(name) => url.push_str(&encode_catch_all(value(name)?)),
+            }
+        }
+
+        let names = self.param_names();
+
+        if let Some(extra) = params
+            .keys()
+            .find(|key| !names.iter().any(|name| name == *key))
+        {
+            return Err(UrlGenerationError::UnexpectedParameter(extra.clone()));
+        }
+
+        if url.is_empty() {
+            url.push('/');
+        }
+
+        Ok(url)
+    }
+
     /// Check if the request path matches the route
-    pub fn is_match(&self, request_path: &str) -> Option<BTreeMap<String, String>> {
+    pub fn is_match(&self, request_path: &str) -> Option<Params> {
         let request_segments = request_path
             .split('/')
             .filter(|segment| !segment.is_empty())
             .collect::<Vec<_>>();
-        let mut params = BTreeMap::new();
+        let mut params = Params::new();
 
         if self.matches_segments(&request_segments, &mut params) {
             Some(params)
@@ -75,16 +217,14 @@ impl Route {
     }
 
     /// Check if the request path matches the route
-    fn matches_segments(
-        &self,
-        request_segments: &[&str],
-        params: &mut BTreeMap<String, String>,
-    ) -> bool {
+    fn matches_segments(&self, request_segments: &[&str], params: &mut Params) -> bool {
         let route_iter = self.path.iter();
         let mut request_iter = request_segments.iter();
 
         for route_segment in route_iter {
             match route_segment {
+                // like a trailing slash of the request, a trailing slash of the route is ignored
+                PathSegment::Static(route_str) if route_str.is_empty() => {}
                 PathSegment::Static(route_str) => {
                     if let Some(req_segment) = request_iter.next() {
                         if route_str != req_segment {
@@ -101,14 +241,40 @@ impl Route {
                         return false;
                     }
                 }
+                PathSegment::Constrained(param_name, constraint) => match request_iter.next() {
+                    Some(req_segment) if constraint.is_match(req_segment) => {
+                        params.insert(param_name.to_string(), req_segment.to_string());
+                    }
+                    _ => return false,
+                },
+                PathSegment::Compound(compound) => {
+                    match request_iter
+                        .next()
+                        .and_then(|segment| compound.captures(segment))
+                    {
+                        Some(captures) => params.extend(captures),
+                        None => return false,
+                    }
+                }
                 PathSegment::Wildcard => {
                     if request_iter.next().is_none() {
                         return false;
                     }
                 }
-                PathSegment::WildcardDeep => {
+                PathSegment::WildcardDeep | PathSegment::CatchAll(_) => {
                     let remaining: Vec<_> = request_iter.cloned().collect();
-                    params.insert("wildcard_deep".to_string(), remaining.join("/"));
+
+                    // like the route tree, the rest of the path has to have at least one segment
+                    if remaining.is_empty() {
+                        return false;
+                    }
+
+                    let name = match route_segment {
+                        PathSegment::CatchAll(param_name) => param_name,
+                        _ => "wildcard_deep",
+                    };
+
+                    params.insert(name.to_string(), remaining.join("/"));
                     return true;
                 }
             }
@@ -118,6 +284,20 @@ impl Route {
     }
 }
 
+impl Display for Route {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            return write!(f, "/");
+        }
+
+        for segment in &self.path {
+            write!(f, "/{}", segment)?;
+        }
+
+        Ok(())
+    }
+}
+
 impl Ord for Route {
     fn cmp(&self, other: &Self) -> Ordering {
         self.path.len().cmp(&other.path.len())
@@ -158,3 +338,12 @@ impl FromIterator<PathSegment> for Route {
         }
     }
 }
+
+/// Percent-encodes the value of a catch-all, every part between slashes is encoded on its own
+fn encode_catch_all(value: &str) -> String {
+    value
+        .split('/')
+        .map(encode_segment)
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,43 @@
+//! The route that was matched for a request
+
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Which route handled a request, see [`HttpRequest::route_match`](crate::request::HttpRequest::route_match)
+pub enum RouteMatch {
+    /// The request was not routed, like a request created outside of the server
+    #[default]
+    Unrouted,
+    /// No route matched the request and it is answered by the not found handler
+    NotFound,
+    /// A route matched the request
+    Matched {
+        /// The pattern of the route as it was registered, like `/users/{id}`
+        pattern: Arc<str>,
+        /// The name of the route if it was given one
+        name: Option<Arc<str>>,
+    },
+}
+
+impl RouteMatch {
+    /// Returns the pattern of the matched route
+    pub fn pattern(&self) -> Option<&str> {
+        match self {
+            RouteMatch::Matched { pattern, .. } => Some(pattern),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the matched route
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            RouteMatch::Matched { name, .. } => name.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if no route matched the request
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, RouteMatch::NotFound)
+    }
+}
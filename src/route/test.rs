@@ -3,6 +3,7 @@
 #![cfg(test)]
 
 use super::*;
+use crate::error::ConflictKind;
 use crate::request::HttpRequest;
 use crate::response::HttpResponse;
 
@@ -38,6 +39,39 @@ fn test_wildcard_deep_match() {
     );
 }
 
+#[test]
+fn test_wildcard_deep_needs_a_segment() {
+    let route = Route::new("/a/**");
+    assert!(route.is_match("/a").is_none());
+    assert!(route.is_match("/a/b").is_some());
+
+    let mut root = RouteNode::new();
+    root.insert(
+        &route,
+        |_req: HttpRequest| async move { HttpResponse::new(200) },
+    );
+
+    // the tree and `Route::is_match` agree
+    assert!(root
+        .match_path(&Route::from_request_path("/a"), None)
+        .is_none());
+    assert!(root
+        .match_path(&Route::from_request_path("/a/b"), None)
+        .is_some());
+}
+
+#[test]
+fn test_segments_after_catch_all() {
+    let err = Route::parse("/a/**/b").unwrap_err();
+    assert_eq!(
+        err.reason,
+        "`**` matches the rest of the path, it has to be the last segment"
+    );
+
+    assert!(Route::parse("/files/{*path}/raw").is_err());
+    assert!(Route::parse("/files/{*path}").is_ok());
+}
+
 #[test]
 fn test_route_addition() {
     let route1 = Route::new("/echo");
@@ -67,7 +101,7 @@ fn test_match_wildcard_path() {
         HttpResponse::new(200)
     });
     let route = Route::new("/wildcard/some_value");
-    assert!(root.match_path(&route).is_some());
+    assert!(root.match_path(&route, None).is_some());
 }
 
 #[test]
@@ -78,7 +112,261 @@ fn test_match_deep_wildcard_path() {
         |_req: HttpRequest| async move { HttpResponse::new(200) },
     );
     let route = Route::new("/wildcard_deep/any/level/of/segments");
-    assert!(root.match_path(&route).is_some());
+    assert!(root.match_path(&route, None).is_some());
+}
+
+#[test]
+fn test_constrained_parameter_match() {
+    let route = Route::new("/users/{id:int}");
+    assert_eq!(
+        route.is_match("/users/-42").unwrap().get("id"),
+        Some(&"-42".to_string())
+    );
+    assert!(route.is_match("/users/me").is_none());
+
+    let route = Route::new(r"/posts/{slug:[a-z-]+}/{code:\d{3}}");
+    assert!(route.is_match("/posts/hello-world/123").is_some());
+    assert!(route.is_match("/posts/Hello/123").is_none());
+    assert!(route.is_match("/posts/hello/1234").is_none());
+
+    let route = Route::new("/items/{uuid:uuid}");
+    assert!(route
+        .is_match("/items/67e55044-10b1-426f-9247-bb680e5fe0c8")
+        .is_some());
+    assert!(route.is_match("/items/67e55044-10b1-426f-9247").is_none());
+}
+
+#[test]
+fn test_int_constraint_matches_like_compound_segments() {
+    let whole = Route::new("/users/{id:int}");
+    let compound = Route::new("/users/u{id:int}");
+
+    for (value, matches) in [
+        ("42", true),
+        ("-42", true),
+        ("+5", false),
+        ("99999999999999999999", true),
+        ("4.2", false),
+        ("", false),
+    ] {
+        assert_eq!(
+            whole.is_match(&format!("/users/{}", value)).is_some(),
+            matches
+        );
+        assert_eq!(
+            compound.is_match(&format!("/users/u{}", value)).is_some(),
+            matches
+        );
+    }
+}
+
+#[test]
+fn test_invalid_constraint() {
+    let err = Route::parse("/users/{id:[a-}").unwrap_err();
+    assert_eq!(err.route, "/users/{id:[a-}");
+    assert!(err.reason.starts_with("invalid constraint `[a-`"));
+
+    assert!(Route::parse("/files/{name:(}.txt").is_err());
+    assert!(Route::parse("/files/{name.txt").is_err());
+    assert!(Route::parse("/users/{id:}").is_err());
+}
+
+#[test]
+fn test_parameters_need_unique_names() {
+    let err = Route::parse("/a/{id}/{id}").unwrap_err();
+    assert_eq!(err.reason, "duplicate parameter `id`");
+
+    for route in [
+        "/a/{id}/b/{id:int}",
+        "/files/{name}.{name}",
+        "/files/{name}/{*name}",
+        "/{wildcard_deep}/**",
+        "/a/{}",
+        "/a/{:int}",
+        "/files/{*}",
+        "/files/v{}",
+        "/files/{*path:int}",
+        "/files/a{*path}",
+        "/users/id}",
+    ] {
+        assert!(Route::parse(route).is_err(), "{} should be invalid", route);
+    }
+
+    assert!(Route::parse("/a/{id}/b/{other}/**").is_ok());
+}
+
+#[test]
+fn test_constrained_parameter_fallthrough() {
+    let mut root = RouteNode::new();
+    root.insert(
+        &Route::new("/users/{id:int}/posts"),
+        |_req: HttpRequest| async move { HttpResponse::new(200) },
+    );
+    root.insert(
+        &Route::new("/users/{name}"),
+        |_req: HttpRequest| async move { HttpResponse::new(200) },
+    );
+
+    let (_, params, _) = root
+        .match_path(&Route::new("/users/12/posts"), None)
+        .unwrap();
+    assert_eq!(params.get("id"), Some(&"12".to_string()));
+
+    let (_, params, _) = root.match_path(&Route::new("/users/12"), None).unwrap();
+    assert_eq!(params.get("name"), Some(&"12".to_string()));
+
+    assert!(root
+        .match_path(&Route::new("/users/me/posts"), None)
+        .is_none());
+}
+
+#[test]
+fn test_catch_all_match() {
+    let route = Route::new("/files/{*path}");
+    let params = route.is_match("/files/a/b/c.txt").unwrap();
+    assert_eq!(params.get("path"), Some(&"a/b/c.txt".to_string()));
+    assert!(route.is_match("/files").is_none());
+
+    let mut root = RouteNode::new();
+    root.insert(
+        &Route::new("/files/{*path}"),
+        |_req: HttpRequest| async move { HttpResponse::new(200) },
+    );
+
+    let (_, params, _) = root
+        .match_path(&Route::from_request_path("/files/a/b/c.txt"), None)
+        .unwrap();
+    assert_eq!(params.get("path"), Some(&"a/b/c.txt".to_string()));
+    assert!(root
+        .match_path(&Route::from_request_path("/files"), None)
+        .is_none());
+}
+
+#[test]
+fn test_compound_segment_match() {
+    let route = Route::new("/files/{name}.{ext}");
+    let params = route.is_match("/files/archive.tar.gz").unwrap();
+    assert_eq!(params.get("name"), Some(&"archive".to_string()));
+    assert_eq!(params.get("ext"), Some(&"tar.gz".to_string()));
+    assert!(route.is_match("/files/archive").is_none());
+
+    let mut root = RouteNode::new();
+    root.insert(
+        &Route::new("/v{version:int}/items"),
+        |_req: HttpRequest| async move { HttpResponse::new(200) },
+    );
+    root.insert(
+        &Route::new("/{page}/items"),
+        |_req: HttpRequest| async move { HttpResponse::new(200) },
+    );
+
+    let (_, params, _) = root
+        .match_path(&Route::from_request_path("/v2/items"), None)
+        .unwrap();
+    assert_eq!(params.get("version"), Some(&"2".to_string()));
+
+    let (_, params, _) = root
+        .match_path(&Route::from_request_path("/vx/items"), None)
+        .unwrap();
+    assert_eq!(params.get("page"), Some(&"vx".to_string()));
+}
+
+#[test]
+fn test_compound_segment_with_grouped_constraint() {
+    let route = Route::new("/files/{kind:(a|b)+}.{ext}");
+    let params = route.is_match("/files/abba.txt").unwrap();
+    assert_eq!(params.get("kind"), Some(&"abba".to_string()));
+    assert_eq!(params.get("ext"), Some(&"txt".to_string()));
+    assert!(route.is_match("/files/abc.txt").is_none());
+}
+
+#[test]
+fn test_match_backtracking() {
+    let mut root = RouteNode::new();
+    root.insert(
+        &Route::new("/users/me/settings"),
+        |_req: HttpRequest| async move { HttpResponse::new(200) },
+    );
+    root.insert(
+        &Route::new("/users/{id}/posts"),
+        |_req: HttpRequest| async move { HttpResponse::new(200) },
+    );
+    root.insert(
+        &Route::new("/users/*/comments"),
+        |_req: HttpRequest| async move { HttpResponse::new(200) },
+    );
+
+    let (_, params, _) = root
+        .match_path(&Route::from_request_path("/users/me/posts"), None)
+        .unwrap();
+    assert_eq!(params.get("id"), Some(&"me".to_string()));
+
+    let (_, params, _) = root
+        .match_path(&Route::from_request_path("/users/me/comments"), None)
+        .unwrap();
+    assert!(params.is_empty());
+
+    assert!(root
+        .match_path(&Route::from_request_path("/users/me/likes"), None)
+        .is_none());
+}
+
+#[test]
+fn test_parameter_names_per_route() {
+    let mut root = RouteNode::new();
+    root.insert(&Route::new("/users/{id}"), |_req: HttpRequest| async move {
+        HttpResponse::new(200)
+    });
+    root.insert(
+        &Route::new("/users/{user_id}/posts/{post_id}"),
+        |_req: HttpRequest| async move { HttpResponse::new(200) },
+    );
+
+    let (_, params, _) = root
+        .match_path(&Route::from_request_path("/users/1/posts/2"), None)
+        .unwrap();
+    assert_eq!(params.get("user_id"), Some(&"1".to_string()));
+    assert_eq!(params.get("post_id"), Some(&"2".to_string()));
+    assert!(!params.contains_key("id"));
+
+    let (_, params, route_match) = root
+        .match_path(&Route::from_request_path("/users/1"), None)
+        .unwrap();
+    assert_eq!(route_match.pattern(), Some("/users/{id}"));
+    assert_eq!(params.get("id"), Some(&"1".to_string()));
+    assert!(root.conflicts().is_empty());
+
+    root.insert(
+        &Route::new("/users/{name}"),
+        |_req: HttpRequest| async move { HttpResponse::new(200) },
+    );
+
+    let conflicts = root.conflicts();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].0.to_string(), "/users/{id}");
+    assert_eq!(conflicts[0].1.to_string(), "/users/{name}");
+    assert_eq!(conflicts[0].2, ConflictKind::Ambiguous);
+}
+
+#[test]
+fn test_wildcard_and_deep_wildcard() {
+    let mut root = RouteNode::new();
+    root.insert(&Route::new("/static/*"), |_req: HttpRequest| async move {
+        HttpResponse::new(200)
+    });
+    root.insert(&Route::new("/static/**"), |_req: HttpRequest| async move {
+        HttpResponse::new(200)
+    });
+
+    let (_, params, _) = root
+        .match_path(&Route::from_request_path("/static/app.js"), None)
+        .unwrap();
+    assert!(params.is_empty());
+
+    let (_, params, _) = root
+        .match_path(&Route::from_request_path("/static/js/app.js"), None)
+        .unwrap();
+    assert_eq!(params.get("wildcard_deep"), Some(&"js/app.js".to_string()));
 }
 
 /*#[tokio::test]
@@ -102,7 +390,7 @@ async fn test_route_json_handler() {
     node.insert(&route, json_handler);
 
     let test_route = Route::new("/echo/test");
-    let handler = node.match_path(&test_route);
+    let handler = node.match_path(&test_route, None);
     assert!(handler.is_some());
 
     let (handler, params) = handler.unwrap();
@@ -146,7 +434,7 @@ async fn test_route_node_insertion() {
 
     let test_route = Route::new("/echo/test");
 
-    let handler = node.match_path(&test_route);
+    let handler = node.match_path(&test_route, None);
     assert!(handler.is_some());
 
     let (handler, params) = handler.unwrap();
@@ -166,3 +454,12 @@ async fn test_route_node_insertion() {
     let res = res.unwrap();
     assert_eq!(res.status_code, 200);
 }*/
+
+#[test]
+fn test_trailing_slash_in_pattern() {
+    assert_eq!(Route::new("/admin/").to_string(), "/admin/");
+    assert_eq!(Route::new("/admin").to_string(), "/admin");
+    assert_eq!(Route::new("/").to_string(), "/");
+    assert_eq!(Route::new("/files/{*path}/").to_string(), "/files/{*path}");
+    assert!(Route::new("/admin/").is_match("/admin").is_some());
+}
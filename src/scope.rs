@@ -0,0 +1,231 @@
+//! Scopes group routes under a common path prefix with their own middleware and state.
+
+use crate::error::Error;
+use crate::guard::Guard;
+use crate::prelude::{FromRequest, Handler, HttpPayload, HttpRequest, HttpResponse};
+use crate::resource::RouteBuilder;
+use crate::route::{Endpoint, HandlerFn, LayerFn};
+use crate::services::HttpService;
+use crate::state::State;
+use http::Method;
+use std::future::Future;
+use std::sync::Arc;
+use tower::{Layer, Service};
+
+#[allow(unused_imports)]
+use crate::prelude::HttpServer;
+
+/// A group of routes under a common path prefix, registered on an [`HttpServer`] with
+/// [`HttpServerBuilder::scope`](crate::server::builder::HttpServerBuilder::scope).
+///
+/// Layers added with [`Scope::wrap`] only run for routes in the scope, inside the layers of the server
+/// and of any parent scope. State added with [`Scope::app_state`] is only visible to routes in the scope
+/// and takes precedence over state of the same type added to the server or a parent scope.
+///
+/// # Examples
+/// ```
+/// # use tosic_http::prelude::{HttpServer, Responder, HttpResponse, BoxBody};
+/// # use tosic_http::resource::get;
+/// # use tosic_http::scope::Scope;
+/// async fn dashboard() -> impl Responder<Body = BoxBody> {
+///     HttpResponse::new(200)
+/// }
+///
+/// async fn users() -> impl Responder<Body = BoxBody> {
+///     HttpResponse::new(200)
+/// }
+///
+/// let admin = Scope::new("/admin")
+///     .route(get("/", dashboard))
+///     .scope(Scope::new("/users").route(get("/", users)));
+///
+/// let builder = HttpServer::builder()
+///     .scope(admin)
+///     .bind("127.0.0.1:8080");
+/// ```
+///
+/// [`controller`](crate::prelude::controller) builds a scope from the service functions in a module or
+/// `impl` block, their routes are joined with the prefix of the controller:
+/// ```
+/// # #![feature(impl_trait_in_assoc_type)]
+/// # use tosic_http::prelude::{controller, get, post, HttpServer, Path};
+/// struct Users;
+///
+/// #[controller("/api/v1/users")]
+/// impl Users {
+///     #[get("/{id}")]
+///     async fn show(id: Path<u32>) -> String {
+///         format!("user {}", id.into_inner())
+///     }
+///
+///     #[post("/")]
+///     async fn create() -> &'static str {
+///         "created"
+///     }
+/// }
+///
+/// # fn main() {
+/// let builder = HttpServer::builder()
+///     .scope(Users::scope())
+///     .bind("127.0.0.1:8080");
+/// # }
+/// ```
+pub struct Scope {
+    prefix: String,
+    routes: Vec<(Method, String, Endpoint)>,
+    layers: Vec<Arc<LayerFn>>,
+    guards: Vec<Arc<dyn Guard>>,
+    state: State,
+}
+
+impl Scope {
+    /// Creates a new empty scope for routes under `prefix`
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            routes: Vec::new(),
+            layers: Vec::new(),
+            guards: Vec::new(),
+            state: State::new(),
+        }
+    }
+
+    /// Adds a handler for `method` at `path` relative to the prefix of the scope
+    pub fn service_method<Args>(
+        mut self,
+        method: Method,
+        path: &str,
+        handler: impl Handler<Args>,
+    ) -> Self
+    where
+        Args: FromRequest + Send + 'static,
+        Args::Future: Future + Send + 'static,
+        Error: From<Args::Error>,
+    {
+        self.routes
+            .push((method, path.to_string(), HandlerFn::wrap(handler).into()));
+        self
+    }
+
+    /// Adds a service handler, its path is relative to the prefix of the scope
+    pub fn service<Args, H>(mut self, handler: H) -> Self
+    where
+        H: HttpService<Args>,
+        Args: FromRequest + Send + 'static,
+        Args::Future: Future + Send + 'static,
+        Error: From<Args::Error>,
+    {
+        let methods = handler.methods();
+        let path = handler.path();
+        let endpoint = Endpoint::from(H::layer(HandlerFn::wrap(handler))).with_name(H::NAME);
+
+        for method in methods {
+            self.routes
+                .push((method.clone(), path.to_string(), endpoint.clone()));
+        }
+        self
+    }
+
+    /// Adds a route, its path is relative to the prefix of the scope
+    pub fn route(mut self, route_builder: RouteBuilder) -> Self {
+        let (path, handlers) = route_builder.into_parts();
+
+        for (method, endpoint) in handlers {
+            self.routes.push((method, path.to_string(), endpoint));
+        }
+
+        self
+    }
+
+    /// Nests another scope inside this one, its prefix is relative to the prefix of this scope
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.routes.extend(scope.into_routes());
+        self
+    }
+
+    /// Adds state that is only visible to the routes in this scope.
+    ///
+    /// It takes precedence over state of the same type added to the server or a parent scope.
+    pub fn app_state<S: Send + Sync + 'static>(mut self, state: S) -> Self {
+        self.state.insert(state);
+        self
+    }
+
+    /// Only lets routes in this scope handle requests that pass `guard`, like a [`Host`](crate::guard::Host) for virtual hosts.
+    ///
+    /// See [`guard`](crate::guard) for how guarded routes are dispatched.
+    pub fn guard(mut self, guard: impl Guard) -> Self {
+        self.guards.push(Arc::new(guard));
+        self
+    }
+
+    /// Wraps every route in this scope in `layer`.
+    ///
+    /// Layers run in the order they were added, the first layer added sees the request first.
+    pub fn wrap<S>(mut self, layer: S) -> Self
+    where
+        S: Layer<HandlerFn> + Send + Sync + 'static,
+        S::Service: Service<(HttpRequest, HttpPayload), Response = HttpResponse, Error = Error>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        <S::Service as Service<(HttpRequest, HttpPayload)>>::Future: Send + 'static,
+    {
+        self.layers
+            .push(Arc::new(move |handler: HandlerFn| handler.layer(&layer)));
+        self
+    }
+
+    /// Applies the layers and state of the scope and returns every route with the prefix prepended
+    pub(crate) fn into_routes(self) -> Vec<(Method, String, Endpoint)> {
+        let Scope {
+            prefix,
+            routes,
+            layers,
+            guards,
+            state,
+        } = self;
+
+        routes
+            .into_iter()
+            .map(|(method, path, mut endpoint)| {
+                let handler = apply_layers(&layers, endpoint.handler);
+
+                endpoint.handler = if state.is_empty() {
+                    handler
+                } else {
+                    with_state(handler, state.clone())
+                };
+                endpoint.guards.extend(guards.iter().cloned());
+
+                (method, join_paths(&prefix, &path), endpoint)
+            })
+            .collect()
+    }
+}
+
+/// Wraps `handler` in `layers`, the first layer ends up as the outermost one
+pub(crate) fn apply_layers(layers: &[Arc<LayerFn>], handler: HandlerFn) -> HandlerFn {
+    layers
+        .iter()
+        .rev()
+        .fold(handler, |handler, layer| layer(handler))
+}
+
+/// Makes `state` visible to `handler` on top of the state the request already has
+fn with_state(handler: HandlerFn, state: State) -> HandlerFn {
+    HandlerFn::from_fn(move |mut req, payload| {
+        req.data = state.with_parent(req.data);
+        (**handler)(req, payload)
+    })
+}
+
+/// Joins a scope prefix and a path into a single path
+fn join_paths(prefix: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        prefix.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
@@ -1,22 +1,35 @@
 //! The [`HttpServerBuilder`] is a builder for configuring and initializing an [`HttpServer`].
 //! It allows for setting up the server address, adding services, and configuring shared state.
 
-use crate::error::Error;
+use crate::error::{Error, RouteConflicts, ServerError};
 use crate::handlers::Handlers;
-use crate::server::HttpServer;
+use crate::server::connection::{ConnectionHooks, ConnectionMeta};
+use crate::server::lifecycle::{BoxError, CancellationToken, Lifecycle};
+use crate::server::socket::SocketOptions;
+#[cfg(unix)]
+use crate::server::systemd::{self, SystemdListeners};
+#[cfg(unix)]
+use crate::server::upgrade;
+use crate::server::{HttpServer, PathNormalization};
 use crate::services::HttpService;
-use crate::state::State;
+use crate::state::factory::StateFactories;
+use crate::state::{Reloadable, State};
+use crate::traits::from_ref::FromRef;
 use crate::traits::from_request::FromRequest;
 use crate::traits::handler::Handler;
-use http::Method;
+use http::{Extensions, Method};
 use std::fmt::Debug;
 use std::future::Future;
+use std::sync::Arc;
 use tokio::io;
 use tokio::net::ToSocketAddrs;
+#[cfg(unix)]
+use tokio::signal::unix::SignalKind;
 
 use crate::prelude::{HttpPayload, HttpRequest, HttpResponse};
 use crate::resource::RouteBuilder;
-use crate::route::HandlerFn;
+use crate::route::{Endpoint, HandlerFn};
+use crate::scope::Scope;
 #[allow(unused_imports)]
 use std::any::TypeId;
 #[allow(unused_imports)]
@@ -33,8 +46,19 @@ where
     L: Layer<HandlerFn> + Clone + Send + 'static,
 {
     addr: Option<T>,
+    listeners: Vec<Arc<std::net::TcpListener>>,
+    socket_options: SocketOptions,
+    #[cfg(unix)]
+    systemd_listeners: Option<SystemdListeners>,
+    systemd_notify: bool,
+    #[cfg(unix)]
+    hot_upgrade: Option<SignalKind>,
+    connection_hooks: ConnectionHooks,
+    lifecycle: Lifecycle,
     handlers: Handlers,
+    path_normalization: PathNormalization,
     app_state: State,
+    state_factories: StateFactories,
     service_builder: ServiceBuilder<L>,
 }
 
@@ -42,8 +66,19 @@ impl<T: ToSocketAddrs + Default + Debug + Clone> Default for HttpServerBuilder<T
     fn default() -> Self {
         Self {
             addr: None,
+            listeners: Vec::new(),
+            socket_options: SocketOptions::default(),
+            #[cfg(unix)]
+            systemd_listeners: None,
+            systemd_notify: false,
+            #[cfg(unix)]
+            hot_upgrade: None,
+            connection_hooks: ConnectionHooks::default(),
+            lifecycle: Lifecycle::default(),
             handlers: Handlers::new(),
+            path_normalization: PathNormalization::default(),
             app_state: State::new(),
+            state_factories: StateFactories::default(),
             service_builder: ServiceBuilder::new(),
         }
     }
@@ -82,10 +117,123 @@ where
     /// }
     ///
     /// let builder = HttpServer::builder()
-    ///     .with_state(MyState { state: "Hello, world!".to_string() })
+    ///     .app_state(MyState { state: "Hello, world!".to_string() })
     ///     .bind("127.0.0.1:8080");
     /// ```
-    pub fn app_state<S: Send + Sync + 'static>(self, state: S) -> Self {
+    pub fn app_state<S: Send + Sync + 'static>(mut self, state: S) -> Self {
+        self.app_state.insert(state);
+
+        self
+    }
+
+    /// Adds shared application state that is created asynchronously, like a connection pool.
+    ///
+    /// The factory is awaited during [`HttpServerBuilder::build`] before the startup hooks run,
+    /// if it fails the remaining factories are skipped and `build` returns [`ServerError::StateFactory`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use tosic_http::prelude::HttpServer;
+    /// struct Pool;
+    ///
+    /// async fn connect() -> Result<Pool, std::io::Error> {
+    ///     Ok(Pool)
+    /// }
+    ///
+    /// let builder = HttpServer::builder()
+    ///     .app_state_factory(connect)
+    ///     .bind("127.0.0.1:8080");
+    /// ```
+    pub fn app_state_factory<S, F, Fut, E>(mut self, factory: F) -> Self
+    where
+        S: Send + Sync + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<S, E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        self.state_factories.push(factory);
+
+        self
+    }
+
+    /// Adds shared application state that is already behind an [`Arc`].
+    ///
+    /// Unlike [`HttpServerBuilder::app_state`] the state can be unsized, which allows registering a trait object
+    /// and extracting it with `Data<dyn Trait>` so the implementation can be swapped, for example in tests.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use tosic_http::prelude::{Data, HttpServer};
+    /// trait UserRepo: Send + Sync {
+    ///     fn name(&self, id: u64) -> String;
+    /// }
+    ///
+    /// struct InMemoryRepo;
+    ///
+    /// impl UserRepo for InMemoryRepo {
+    ///     fn name(&self, id: u64) -> String {
+    ///         format!("user {}", id)
+    ///     }
+    /// }
+    ///
+    /// async fn user(repo: Data<dyn UserRepo>) -> String {
+    ///     repo.name(1)
+    /// }
+    ///
+    /// let builder = HttpServer::builder()
+    ///     .app_state_arc::<dyn UserRepo>(Arc::new(InMemoryRepo))
+    ///     .bind("127.0.0.1:8080");
+    /// ```
+    pub fn app_state_arc<S: ?Sized + Send + Sync + 'static>(mut self, state: Arc<S>) -> Self {
+        self.app_state.insert_arc(state);
+
+        self
+    }
+
+    /// Makes `S` extractable with `Data<S>` by deriving it from the `P` added as app state, see [`FromRef`].
+    pub fn app_state_from_ref<P, S>(mut self) -> Self
+    where
+        P: Send + Sync + 'static,
+        S: FromRef<P> + Send + Sync + 'static,
+    {
+        self.app_state.insert_derived::<P, S>();
+
+        self
+    }
+
+    /// Adds shared application state that can be changed while the server is running.
+    ///
+    /// State added with [`HttpServerBuilder::app_state`] is frozen once the server is built and can be read without locking.
+    /// State added here is wrapped in a [`tokio::sync::RwLock`] instead and is extracted with `Data<RwLock<S>>`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tosic_http::prelude::{Data, HttpServer};
+    /// # use tokio::sync::RwLock;
+    /// struct Counter(u64);
+    ///
+    /// async fn increment(counter: Data<RwLock<Counter>>) -> String {
+    ///     let mut counter = counter.write().await;
+    ///     counter.0 += 1;
+    ///     counter.0.to_string()
+    /// }
+    ///
+    /// let builder = HttpServer::builder()
+    ///     .app_state_mut(Counter(0))
+    ///     .bind("127.0.0.1:8080");
+    /// ```
+    pub fn app_state_mut<S: Send + Sync + 'static>(mut self, state: S) -> Self {
+        self.app_state.insert(tokio::sync::RwLock::new(state));
+
+        self
+    }
+
+    /// Adds shared application state that can be replaced while the server is running.
+    ///
+    /// Keep a clone of `state` to store new values, handlers read the current one with the
+    /// [`Snapshot`](crate::extractors::Snapshot) extractor. See [`Reloadable`] for an example.
+    pub fn app_state_reloadable<S: Send + Sync + 'static>(mut self, state: Reloadable<S>) -> Self {
         self.app_state.insert(state);
 
         self
@@ -142,6 +290,7 @@ where
     ///
     /// # Examples
     /// ```
+    /// # #![feature(impl_trait_in_assoc_type)]
     /// # use tosic_http::prelude::{HttpServer, Responder, HttpResponse, BoxBody, Method, get};
     ///
     /// #[get("/")]
@@ -153,14 +302,21 @@ where
     ///     .service(basic_handler)
     ///     .bind("127.0.0.1:8080");
     /// ```
-    pub fn service<Args>(mut self, handler: impl HttpService<Args>) -> Self
+    pub fn service<Args, H>(mut self, handler: H) -> Self
     where
+        H: HttpService<Args>,
         Args: FromRequest + Send + 'static,
         Args::Future: Future + Send + 'static,
         Error: From<Args::Error>,
     {
-        self.handlers
-            .insert(handler.method(), handler.path(), handler);
+        let methods = handler.methods();
+        let path = handler.path();
+        let endpoint = Endpoint::from(H::layer(HandlerFn::wrap(handler))).with_name(H::NAME);
+
+        for method in methods {
+            self.handlers
+                .insert_endpoint(method.clone(), path, endpoint.clone());
+        }
         self
     }
 
@@ -180,6 +336,143 @@ where
         self
     }
 
+    /// Adds a [`Scope`] and every route in it to the server.
+    ///
+    /// # Returns
+    ///
+    /// The builder instance with the routes of the scope added.
+    ///
+    pub fn scope(mut self, scope: Scope) -> Self {
+        for (method, path, endpoint) in scope.into_routes() {
+            self.handlers.insert_endpoint(method, &path, endpoint);
+        }
+
+        self
+    }
+
+    /// Adds a hook that runs every time a connection is accepted.
+    ///
+    /// Values inserted into the [`Extensions`] are readable from every request received on the
+    /// connection through [`HttpRequest::connection_extensions`].
+    /// Hooks run in the order they were added, before any request is read from the connection.
+    /// If a hook panics the connection is closed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tosic_http::prelude::HttpServer;
+    /// #[derive(Clone)]
+    /// struct ConnectionId(u64);
+    ///
+    /// let builder = HttpServer::builder()
+    ///     .on_connect(|meta, extensions| {
+    ///         extensions.insert(ConnectionId(meta.id()));
+    ///     })
+    ///     .bind("127.0.0.1:8080");
+    /// ```
+    pub fn on_connect<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ConnectionMeta, &mut Extensions) + Send + Sync + 'static,
+    {
+        self.connection_hooks.on_connect.push(Arc::new(hook));
+        self
+    }
+
+    /// Adds a hook that runs every time a connection is closed.
+    ///
+    /// The hook receives the extensions populated by the hooks added with [`HttpServerBuilder::on_connect`].
+    /// It also runs when handling the connection panicked.
+    pub fn on_disconnect<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ConnectionMeta, &Extensions) + Send + Sync + 'static,
+    {
+        self.connection_hooks.on_disconnect.push(Arc::new(hook));
+        self
+    }
+
+    /// Adds an async hook that runs with the application state when the server is built.
+    ///
+    /// Startup hooks run in the order they were added before any listener is bound.
+    /// If a hook fails the remaining hooks are skipped and [`HttpServerBuilder::build`] returns the error.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tosic_http::prelude::{HttpServer, State};
+    /// struct Cache;
+    ///
+    /// let builder = HttpServer::builder()
+    ///     .app_state(Cache)
+    ///     .on_startup(|state: State| async move {
+    ///         let _cache = state.get::<Cache>().ok_or("the cache is missing")?;
+    ///         Ok::<_, &str>(())
+    ///     })
+    ///     .bind("127.0.0.1:8080");
+    /// ```
+    pub fn on_startup<F, Fut, E>(mut self, hook: F) -> Self
+    where
+        F: Fn(State) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        self.lifecycle.on_startup.push(Arc::new(move |state| {
+            let future = hook(state);
+            Box::pin(async move { future.await.map_err(Into::into) })
+        }));
+        self
+    }
+
+    /// Adds an async hook that runs with the application state when the server shuts down.
+    ///
+    /// Shutdown hooks run in the order they were added, once all connections are closed
+    /// and every background task has finished. Failures are logged.
+    pub fn on_shutdown<F, Fut, E>(mut self, hook: F) -> Self
+    where
+        F: Fn(State) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        self.lifecycle.on_shutdown.push(Arc::new(move |state| {
+            let future = hook(state);
+            Box::pin(async move { future.await.map_err(Into::into) })
+        }));
+        self
+    }
+
+    /// Adds a task that is spawned when the server starts serving and runs until it shuts down.
+    ///
+    /// The [`CancellationToken`] is cancelled once all connections are closed during shutdown,
+    /// after which the server waits for the task to return.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::time::Duration;
+    /// # use tosic_http::prelude::{HttpServer, State};
+    /// # use tosic_http::server::lifecycle::CancellationToken;
+    /// let builder = HttpServer::builder()
+    ///     .background_task(|_state: State, cancel: CancellationToken| async move {
+    ///         let mut interval = tokio::time::interval(Duration::from_secs(60));
+    ///
+    ///         loop {
+    ///             tokio::select! {
+    ///                 _ = cancel.cancelled() => break,
+    ///                 _ = interval.tick() => { /* periodic work */ }
+    ///             }
+    ///         }
+    ///     })
+    ///     .bind("127.0.0.1:8080");
+    /// ```
+    pub fn background_task<F, Fut>(mut self, task: F) -> Self
+    where
+        F: Fn(State, CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.lifecycle
+            .background_tasks
+            .push(Arc::new(move |state, cancellation| {
+                Box::pin(task(state, cancellation))
+            }));
+        self
+    }
+
     /// Sets the address the server will bind to.
     ///
     /// # Arguments
@@ -201,10 +494,115 @@ where
         self
     }
 
+    /// Adds an already bound listener for the server to accept connections on.
+    ///
+    /// This can be called multiple times and may be combined with [`HttpServerBuilder::bind`].
+    /// If only listeners are provided the server will not bind to the default address.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tosic_http::prelude::HttpServer;
+    /// let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    ///
+    /// let builder = HttpServer::builder::<&str>()
+    ///     .listener(listener);
+    /// ```
+    pub fn listener(mut self, listener: std::net::TcpListener) -> Self {
+        self.listeners.push(Arc::new(listener));
+        self
+    }
+
+    #[cfg(unix)]
+    /// Takes over every listener passed to the process by systemd socket activation.
+    ///
+    /// The listeners are read from `LISTEN_FDS` when [`HttpServerBuilder::build`] is called.
+    /// When systemd passed any listeners the address from [`HttpServerBuilder::bind`] is not bound,
+    /// which allows the same binary to be started both with and without socket activation.
+    pub fn systemd_listeners(mut self) -> Self {
+        self.systemd_listeners = Some(SystemdListeners::All);
+        self
+    }
+
+    #[cfg(unix)]
+    /// Takes over the listener passed by systemd socket activation with the given name in `LISTEN_FDNAMES`.
+    ///
+    /// This can be called multiple times to take over several named listeners,
+    /// see [`HttpServerBuilder::systemd_listeners`] for how this interacts with [`HttpServerBuilder::bind`].
+    pub fn systemd_listener(mut self, name: impl Into<String>) -> Self {
+        match &mut self.systemd_listeners {
+            Some(SystemdListeners::Named(names)) => names.push(name.into()),
+            _ => self.systemd_listeners = Some(SystemdListeners::Named(vec![name.into()])),
+        }
+        self
+    }
+
+    /// Sends readiness notifications to systemd through `NOTIFY_SOCKET`.
+    ///
+    /// `READY=1` is sent once the server starts accepting connections
+    /// and `STOPPING=1` is sent when a graceful shutdown starts.
+    /// Nothing is sent if the process was started without a notification socket.
+    pub fn systemd_notify(mut self) -> Self {
+        self.systemd_notify = true;
+        self
+    }
+
+    #[cfg(unix)]
+    /// Enables zero-downtime upgrades of the running binary when `signal` is received.
+    ///
+    /// On the signal a new copy of the current executable is started with the same arguments and the
+    /// listening sockets are passed to it over a unix socket. The new process must enable hot upgrades
+    /// as well, in which case it takes over the sockets in [`HttpServerBuilder::build`] instead of binding
+    /// its own. Once it is accepting connections the old process shuts down gracefully as if the signal
+    /// passed to [`HttpServer::serve_with_shutdown`] had completed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tosic_http::prelude::HttpServer;
+    /// use tokio::signal::unix::SignalKind;
+    ///
+    /// let builder = HttpServer::builder()
+    ///     .hot_upgrade(SignalKind::user_defined2())
+    ///     .bind("127.0.0.1:8080");
+    /// ```
+    pub fn hot_upgrade(mut self, signal: SignalKind) -> Self {
+        self.hot_upgrade = Some(signal);
+        self
+    }
+
+    /// Sets how request paths are normalized before they are matched against the routes.
+    ///
+    /// By default repeated slashes are merged, dot segments are resolved and trailing slashes are ignored.
+    /// See [`PathNormalization`] for the available options.
+    pub fn path_normalization(mut self, path_normalization: PathNormalization) -> Self {
+        self.path_normalization = path_normalization;
+        self
+    }
+
+    /// Sets the socket options used for the listener and every accepted connection.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tosic_http::prelude::HttpServer;
+    /// # use tosic_http::server::socket::SocketOptions;
+    /// let builder = HttpServer::builder()
+    ///     .socket_options(SocketOptions::new().nodelay(true).backlog(4096))
+    ///     .bind("127.0.0.1:8080");
+    /// ```
+    pub fn socket_options(mut self, socket_options: SocketOptions) -> Self {
+        self.socket_options = socket_options;
+        self
+    }
+
     /// Builds and initializes the [`HttpServer`] with the current configuration.
     ///
+    /// The startup hooks run before the listeners are bound and before the listeners passed by systemd or a
+    /// previous process are taken, so a build that failed in a hook can be retried without losing them.
+    ///
     /// # Errors
-    /// Returns [`io::Error`] if there was an error initializing the server.
+    /// Returns [`ServerError::InvalidRoute`] if a route was registered with a pattern that can not be parsed,
+    /// [`ServerError::RouteConflicts`] if routes were registered that can not be told apart,
+    /// [`ServerError::Startup`] if a startup hook failed
+    /// or [`ServerError::Io`] if there was an error binding the listeners.
     ///
     /// # Examples
     /// ```
@@ -218,10 +616,85 @@ where
     ///     .unwrap();
     /// # }
     /// ```
-    pub async fn build(self) -> io::Result<HttpServer<L>> {
-        let addr = self.addr.unwrap_or_default();
+    pub async fn build(self) -> Result<HttpServer<L>, ServerError> {
+        if let Some(invalid) = self.handlers.invalid_routes().into_iter().next() {
+            return Err(ServerError::InvalidRoute(invalid));
+        }
+
+        let conflicts = self.handlers.conflicts();
+
+        if !conflicts.is_empty() {
+            return Err(ServerError::RouteConflicts(RouteConflicts(conflicts)));
+        }
+
+        // Inherited listeners can only be taken once per process, so everything that can fail runs first
+        // to leave them in place for a retry
+        let mut app_state = self.app_state;
+        self.state_factories.build(&mut app_state).await?;
+        app_state.freeze();
+
+        self.lifecycle.startup(&app_state).await?;
+
+        let mut listeners = self
+            .listeners
+            .into_iter()
+            .map(|listener| match Arc::try_unwrap(listener) {
+                Ok(listener) => Ok(listener),
+                Err(listener) => listener.try_clone(),
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        #[cfg(unix)]
+        let mut activated = match &self.systemd_listeners {
+            Some(systemd_listeners) => systemd_listeners.take()?,
+            None => Vec::new(),
+        };
+        #[cfg(not(unix))]
+        let activated = Vec::new();
+
+        #[cfg(unix)]
+        let upgrade_ack = match self.hot_upgrade {
+            Some(_) => upgrade::receive_listeners()?.map(|(listeners, ack)| {
+                activated.extend(listeners);
+                ack
+            }),
+            None => None,
+        };
+
+        let addr = match self.addr {
+            _ if !activated.is_empty() => None,
+            Some(addr) => Some(addr),
+            None if listeners.is_empty() => Some(T::default()),
+            None => None,
+        };
+
+        listeners.extend(activated);
+
+        let mut server = HttpServer::new(
+            addr,
+            listeners,
+            self.socket_options,
+            self.handlers,
+            app_state,
+            self.service_builder,
+        )
+        .await?;
+
+        server.connection_hooks = Arc::new(self.connection_hooks);
+        server.lifecycle = Arc::new(self.lifecycle);
+        server.path_normalization = self.path_normalization;
+
+        #[cfg(unix)]
+        {
+            server.notify_socket = self
+                .systemd_notify
+                .then(systemd::NotifySocket::from_env)
+                .flatten();
+            server.hot_upgrade = self.hot_upgrade;
+            server.upgrade_ack = upgrade_ack;
+        }
 
-        HttpServer::new(addr, self.handlers, self.app_state, self.service_builder).await
+        Ok(server)
     }
 
     /// Wraps a layer in the stack.
@@ -234,8 +707,19 @@ where
     {
         HttpServerBuilder {
             addr: self.addr,
+            listeners: self.listeners,
+            socket_options: self.socket_options,
+            #[cfg(unix)]
+            systemd_listeners: self.systemd_listeners,
+            systemd_notify: self.systemd_notify,
+            #[cfg(unix)]
+            hot_upgrade: self.hot_upgrade,
+            connection_hooks: self.connection_hooks,
+            lifecycle: self.lifecycle,
             handlers: self.handlers,
+            path_normalization: self.path_normalization,
             app_state: self.app_state,
+            state_factories: self.state_factories,
             service_builder: self.service_builder.layer(layer),
         }
     }
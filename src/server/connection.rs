@@ -0,0 +1,128 @@
+//! Connection metadata and the hooks that run when a connection is opened or closed.
+
+use http::Extensions;
+use std::fmt::Debug;
+use std::net::SocketAddr;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::error;
+
+#[derive(Debug, Clone)]
+/// Information about an accepted connection.
+pub struct ConnectionMeta {
+    id: u64,
+    peer_addr: SocketAddr,
+    local_addr: Option<SocketAddr>,
+    connected_at: Instant,
+}
+
+impl ConnectionMeta {
+    /// Creates the metadata for a connection that was just accepted.
+    pub(crate) fn new(id: u64, peer_addr: SocketAddr, local_addr: Option<SocketAddr>) -> Self {
+        Self {
+            id,
+            peer_addr,
+            local_addr,
+            connected_at: Instant::now(),
+        }
+    }
+
+    /// An id that is unique for every connection accepted by the server.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The address of the remote peer.
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// The local address the connection was accepted on.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// When the connection was accepted.
+    pub fn connected_at(&self) -> Instant {
+        self.connected_at
+    }
+}
+
+#[derive(Debug)]
+/// A connection shared between every request received on it.
+pub(crate) struct Connection {
+    pub(crate) meta: ConnectionMeta,
+    pub(crate) extensions: Extensions,
+}
+
+/// A hook that runs when a connection is opened.
+pub(crate) type OnConnect = dyn Fn(&ConnectionMeta, &mut Extensions) + Send + Sync;
+
+/// A hook that runs when a connection is closed.
+pub(crate) type OnDisconnect = dyn Fn(&ConnectionMeta, &Extensions) + Send + Sync;
+
+#[derive(Clone, Default)]
+/// The registered connection hooks, run in the order they were added.
+pub(crate) struct ConnectionHooks {
+    pub(crate) on_connect: Vec<Arc<OnConnect>>,
+    pub(crate) on_disconnect: Vec<Arc<OnDisconnect>>,
+}
+
+impl Debug for ConnectionHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionHooks")
+            .field("on_connect", &self.on_connect.len())
+            .field("on_disconnect", &self.on_disconnect.len())
+            .finish()
+    }
+}
+
+impl ConnectionHooks {
+    /// Runs the `on_connect` hooks and returns the connection with the extensions they populated.
+    ///
+    /// Returns `None` if a hook panicked, the connection should then be closed.
+    pub(crate) fn connect(&self, meta: ConnectionMeta) -> Option<Arc<Connection>> {
+        let mut extensions = Extensions::new();
+
+        for hook in &self.on_connect {
+            if catch_unwind(AssertUnwindSafe(|| hook(&meta, &mut extensions))).is_err() {
+                error!(
+                    "An on_connect hook panicked, closing the connection from {}",
+                    meta.peer_addr
+                );
+                return None;
+            }
+        }
+
+        Some(Arc::new(Connection { meta, extensions }))
+    }
+
+    /// Runs the `on_disconnect` hooks, a panicking hook does not stop the remaining hooks.
+    pub(crate) fn disconnect(&self, connection: &Connection) {
+        for hook in &self.on_disconnect {
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                hook(&connection.meta, &connection.extensions)
+            }));
+
+            if result.is_err() {
+                error!(
+                    "An on_disconnect hook panicked for the connection from {}",
+                    connection.meta.peer_addr
+                );
+            }
+        }
+    }
+}
+
+/// Runs the `on_disconnect` hooks when dropped, even if handling the connection panicked.
+pub(crate) struct DisconnectGuard {
+    pub(crate) hooks: Arc<ConnectionHooks>,
+    pub(crate) connection: Arc<Connection>,
+}
+
+impl Drop for DisconnectGuard {
+    fn drop(&mut self) {
+        self.hooks.disconnect(&self.connection);
+    }
+}
@@ -0,0 +1,73 @@
+//! Startup and shutdown hooks, and background tasks that live as long as the server.
+
+use crate::error::ServerError;
+use crate::state::State;
+use futures::future::BoxFuture;
+use std::error::Error as StdError;
+use std::fmt::Debug;
+use std::sync::Arc;
+use tokio_util::task::TaskTracker;
+use tracing::error;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// The error type returned by lifecycle hooks.
+pub type BoxError = Box<dyn StdError + Send + Sync>;
+
+/// A hook that runs when the server starts or stops.
+pub(crate) type LifecycleHook =
+    dyn Fn(State) -> BoxFuture<'static, Result<(), BoxError>> + Send + Sync;
+
+/// A task that runs in the background until the server shuts down.
+pub(crate) type BackgroundTask =
+    dyn Fn(State, CancellationToken) -> BoxFuture<'static, ()> + Send + Sync;
+
+#[derive(Clone, Default)]
+/// The registered lifecycle hooks and background tasks, run in the order they were added.
+pub(crate) struct Lifecycle {
+    pub(crate) on_startup: Vec<Arc<LifecycleHook>>,
+    pub(crate) on_shutdown: Vec<Arc<LifecycleHook>>,
+    pub(crate) background_tasks: Vec<Arc<BackgroundTask>>,
+}
+
+impl Debug for Lifecycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Lifecycle")
+            .field("on_startup", &self.on_startup.len())
+            .field("on_shutdown", &self.on_shutdown.len())
+            .field("background_tasks", &self.background_tasks.len())
+            .finish()
+    }
+}
+
+impl Lifecycle {
+    /// Runs the startup hooks, stopping at the first one that fails.
+    pub(crate) async fn startup(&self, state: &State) -> Result<(), ServerError> {
+        for hook in &self.on_startup {
+            hook(state.clone()).await.map_err(ServerError::Startup)?;
+        }
+
+        Ok(())
+    }
+
+    /// Spawns every background task onto `tracker`.
+    pub(crate) fn spawn_background_tasks(
+        &self,
+        state: &State,
+        tracker: &TaskTracker,
+        cancellation: &CancellationToken,
+    ) {
+        for task in &self.background_tasks {
+            tracker.spawn(task(state.clone(), cancellation.child_token()));
+        }
+    }
+
+    /// Runs every shutdown hook, failures are logged and do not stop the remaining hooks.
+    pub(crate) async fn shutdown(&self, state: &State) {
+        for hook in &self.on_shutdown {
+            if let Err(err) = hook(state.clone()).await {
+                error!("Shutdown hook failed: {}", err);
+            }
+        }
+    }
+}
@@ -2,26 +2,44 @@
 
 use crate::body::message_body::MessageBody;
 use crate::error::{Error, ServerError};
-use crate::handlers::Handlers;
+use crate::handlers::{Handlers, NamedRoutes};
 use crate::request::{HttpPayload, HttpRequest};
 use crate::response::HttpResponse;
 use crate::route::HandlerFn;
 use crate::server::builder::HttpServerBuilder;
+use crate::server::connection::{Connection, ConnectionHooks, ConnectionMeta, DisconnectGuard};
+use crate::server::lifecycle::{CancellationToken, Lifecycle};
+use crate::server::socket::SocketOptions;
 use crate::state::State;
-use http::HeaderMap;
+use futures::future::{select_all, BoxFuture};
+use http::{HeaderMap, Method};
 use std::fmt::Debug;
+use std::future::{pending, Future};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use tokio::io;
 use tokio::io::BufReader;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::ToSocketAddrs;
+use tokio::net::{lookup_host, TcpListener, ToSocketAddrs};
+use tokio_util::task::TaskTracker;
 use tower::layer::util::Identity;
 use tower::{Layer, Service, ServiceBuilder, ServiceExt};
 #[cfg(feature = "trace")]
 use tracing::trace;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 pub mod builder;
+pub mod connection;
+pub mod lifecycle;
+pub mod socket;
+#[cfg(unix)]
+pub(crate) mod systemd;
 mod test;
+#[cfg(unix)]
+pub(crate) mod upgrade;
+
+pub use crate::route::{PathNormalization, TrailingSlash};
 
 /// Represents a running HTTP server.
 ///
@@ -30,8 +48,21 @@ pub struct HttpServer<L>
 where
     L: Layer<HandlerFn> + Clone + Send + 'static,
 {
-    listener: tokio::net::TcpListener,
+    listeners: Vec<TcpListener>,
+    socket_options: SocketOptions,
+    #[cfg(unix)]
+    notify_socket: Option<systemd::NotifySocket>,
+    #[cfg(unix)]
+    hot_upgrade: Option<tokio::signal::unix::SignalKind>,
+    #[cfg(unix)]
+    upgrade_ack: Option<std::os::unix::net::UnixStream>,
+    connections: TaskTracker,
+    connection_hooks: Arc<ConnectionHooks>,
+    next_connection_id: AtomicU64,
+    lifecycle: Arc<Lifecycle>,
     handlers: Handlers,
+    named_routes: Arc<NamedRoutes>,
+    path_normalization: PathNormalization,
     app_state: State,
     service_builder: ServiceBuilder<L>,
 }
@@ -57,34 +88,188 @@ where
     )]
     /// Create a new [`HttpServer`] instance and binds the server to the provided address.
     ///
+    /// Already bound `listeners` are used as is, `addr` is only bound if it is provided.
+    ///
     /// This meant to be called from [`HttpServerBuilder`] and not externally
     pub(crate) async fn new(
-        addr: impl ToSocketAddrs + Debug,
+        addr: Option<impl ToSocketAddrs + Debug>,
+        listeners: Vec<std::net::TcpListener>,
+        socket_options: SocketOptions,
         handlers: Handlers,
         app_state: State,
         service_builder: ServiceBuilder<L>,
     ) -> io::Result<Self> {
-        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let mut bound = Vec::with_capacity(listeners.len() + 1);
+
+        if let Some(addr) = addr {
+            bound.push(Self::bind(addr, &socket_options).await?);
+        }
+
+        for listener in listeners {
+            listener.set_nonblocking(true)?;
+            bound.push(TcpListener::from_std(listener)?);
+        }
 
         #[cfg(feature = "trace")]
-        trace!("Server Bound to {}", listener.local_addr()?);
+        for listener in &bound {
+            trace!("Server Bound to {}", listener.local_addr()?);
+        }
 
         Ok(Self {
-            listener,
+            listeners: bound,
+            socket_options,
+            #[cfg(unix)]
+            notify_socket: None,
+            #[cfg(unix)]
+            hot_upgrade: None,
+            #[cfg(unix)]
+            upgrade_ack: None,
+            connections: TaskTracker::new(),
+            connection_hooks: Arc::default(),
+            next_connection_id: AtomicU64::new(0),
+            lifecycle: Arc::default(),
+            named_routes: Arc::new(handlers.named_routes()),
             handlers,
+            path_normalization: PathNormalization::default(),
             app_state,
             service_builder,
         })
     }
 
+    /// Binds a new listener to the first address `addr` resolves to that could be bound.
+    async fn bind(
+        addr: impl ToSocketAddrs,
+        socket_options: &SocketOptions,
+    ) -> io::Result<TcpListener> {
+        let mut last_err = None;
+
+        for addr in lookup_host(addr).await? {
+            match socket_options.bind(addr) {
+                Ok(listener) => return TcpListener::from_std(listener),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        }))
+    }
+
+    /// Returns the local addresses of every listener the server accepts connections on.
+    pub fn local_addrs(&self) -> io::Result<Vec<SocketAddr>> {
+        self.listeners
+            .iter()
+            .map(|listener| listener.local_addr())
+            .collect()
+    }
+
+    /// Returns every registered method and route pattern, sorted by pattern and then by method.
+    ///
+    /// # Examples
+    /// ```
+    /// # use tosic_http::prelude::{HttpServer, Method};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// async fn index() -> &'static str {
+    ///     "Hello, world!"
+    /// }
+    ///
+    /// let server = HttpServer::builder()
+    ///     .service_method(Method::GET, "/", index)
+    ///     .bind("127.0.0.1:0")
+    ///     .build()
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// for (method, pattern) in server.routes() {
+    ///     println!("{method:<7} {pattern}");
+    /// }
+    /// # }
+    /// ```
+    pub fn routes(&self) -> Vec<(Method, String)> {
+        self.handlers.routes()
+    }
+
     /// Starts the server and listens for incoming connections.
     pub async fn serve(self) -> Result<(), ServerError> {
-        info!("Listening on {}", self.listener.local_addr()?);
+        self.serve_with_shutdown(pending()).await
+    }
+
+    /// Starts the server and listens for incoming connections until `signal` completes.
+    ///
+    /// Once `signal` completes the server stops accepting new connections and waits for all open
+    /// connections to finish. Background tasks are then cancelled and awaited, and the shutdown hooks run
+    /// before this returns. The same happens after the listeners were handed over to a new process when
+    /// [`HttpServerBuilder::hot_upgrade`] is enabled.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use tosic_http::prelude::HttpServer;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let server = HttpServer::builder()
+    ///     .bind("127.0.0.1:8080")
+    ///     .build()
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// server
+    ///     .serve_with_shutdown(async {
+    ///         tokio::signal::ctrl_c().await.unwrap();
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub async fn serve_with_shutdown(
+        mut self,
+        signal: impl Future<Output = ()>,
+    ) -> Result<(), ServerError> {
+        if self.listeners.is_empty() {
+            return Err(ServerError::NoListeners);
+        }
+
+        for listener in &self.listeners {
+            info!("Listening on {}", listener.local_addr()?);
+        }
+
+        self.notify_systemd("READY=1");
+        self.acknowledge_upgrade();
+
+        let mut upgraded = self.watch_upgrade()?;
+
+        let background_tasks = TaskTracker::new();
+        let cancellation = CancellationToken::new();
+        self.lifecycle
+            .spawn_background_tasks(&self.app_state, &background_tasks, &cancellation);
+
+        tokio::pin!(signal);
+
         loop {
-            match self.listener.accept().await {
+            let accepts = self
+                .listeners
+                .iter()
+                .map(|listener| Box::pin(listener.accept()));
+
+            let accepted = tokio::select! {
+                _ = &mut signal => break,
+                pid = &mut upgraded => {
+                    info!("Process {} took over the listeners", pid);
+                    break;
+                }
+                (accepted, _, _) = select_all(accepts) => accepted,
+            };
+
+            match accepted {
                 Ok((stream, socket)) => {
                     #[cfg(feature = "trace")]
                     trace!("Accepted connection from {}", socket);
+                    if let Err(err) = self.socket_options.apply_to_stream(&stream) {
+                        warn!("Failed to apply socket options for {}: {}", socket, err);
+                    }
                     self.accept_connection(stream, socket)?;
                 }
                 Err(err) => {
@@ -93,26 +278,111 @@ where
                 }
             }
         }
+
+        info!(
+            "Shutting down, waiting for {} open connections",
+            self.connections.len()
+        );
+        self.notify_systemd("STOPPING=1");
+
+        self.listeners.clear();
+        self.connections.close();
+        self.connections.wait().await;
+
+        cancellation.cancel();
+        background_tasks.close();
+        background_tasks.wait().await;
+
+        self.lifecycle.shutdown(&self.app_state).await;
+
+        Ok(())
+    }
+
+    /// Returns a future that completes with the pid of the new process once a hot upgrade succeeded.
+    fn watch_upgrade(&self) -> io::Result<BoxFuture<'static, u32>> {
+        #[cfg(unix)]
+        if let Some(kind) = self.hot_upgrade {
+            use std::os::fd::AsFd;
+
+            let listeners = self
+                .listeners
+                .iter()
+                .map(|listener| listener.as_fd().try_clone_to_owned())
+                .collect::<io::Result<Vec<_>>>()?;
+
+            return Ok(Box::pin(async move {
+                match upgrade::watch(kind, listeners).await {
+                    Ok(pid) => pid,
+                    Err(err) => {
+                        error!("Hot upgrades are disabled: {}", err);
+                        pending().await
+                    }
+                }
+            }));
+        }
+
+        Ok(Box::pin(pending()))
+    }
+
+    /// Tells the process that handed over the listeners that this server is accepting connections.
+    fn acknowledge_upgrade(&mut self) {
+        #[cfg(unix)]
+        if let Some(stream) = self.upgrade_ack.take() {
+            if let Err(err) = upgrade::acknowledge(stream) {
+                warn!("Failed to acknowledge the upgrade: {}", err);
+            }
+        }
+    }
+
+    /// Sends a state change to systemd if notifications are enabled.
+    fn notify_systemd(&self, state: &str) {
+        #[cfg(unix)]
+        if let Some(socket) = &self.notify_socket {
+            if let Err(err) = socket.notify(state) {
+                warn!("Failed to notify systemd about {}: {}", state, err);
+            }
+        }
+
+        #[cfg(not(unix))]
+        let _ = state;
     }
 
     /// Main entry point for an incoming connection.
     ///
-    /// In this step we spawn a new thread and handle the connection inside it to not block the main thread.
+    /// We spawn a new task and handle the connection inside it to not block the accept loop.
+    /// The `on_connect` hooks run inside the task and the `on_disconnect` hooks run when it ends, even if it panicked.
     fn accept_connection(
         &self,
         stream: tokio::net::TcpStream,
         socket: std::net::SocketAddr,
     ) -> Result<(), ServerError> {
         let handlers = self.handlers.clone();
+        let named_routes = self.named_routes.clone();
+        let path_normalization = self.path_normalization;
         let state = self.app_state.clone();
         let service_builder = self.service_builder.clone();
+        let hooks = self.connection_hooks.clone();
+
+        let id = self.next_connection_id.fetch_add(1, Ordering::Relaxed);
+        let meta = ConnectionMeta::new(id, socket, stream.local_addr().ok());
+
+        self.connections.spawn(async move {
+            let Some(connection) = hooks.connect(meta) else {
+                return;
+            };
+            let _guard = DisconnectGuard {
+                hooks,
+                connection: connection.clone(),
+            };
 
-        tokio::spawn(async move {
             if let Err(e) = Self::handle_connection(
                 stream,
                 #[cfg(feature = "trace")]
                 socket,
+                connection.clone(),
                 handlers,
+                named_routes,
+                path_normalization,
                 state,
                 service_builder,
             )
@@ -130,7 +400,10 @@ where
     async fn handle_connection(
         stream: tokio::net::TcpStream,
         #[cfg(feature = "trace")] socket: std::net::SocketAddr,
+        connection: Arc<Connection>,
         handlers: Handlers,
+        named_routes: Arc<NamedRoutes>,
+        path_normalization: PathNormalization,
         state: State,
         service_builder: ServiceBuilder<L>,
     ) -> Result<(), ServerError> {
@@ -156,13 +429,16 @@ where
         };
 
         request.data = state;
+        request.named_routes = named_routes;
+        request.connection = Some(connection);
 
         #[cfg(feature = "trace")]
         trace!("Request: {:?}", request);
 
-        let handler = handlers.get_handler(request.method(), request.uri().path());
+        let handler = handlers.get_handler(&request, &path_normalization);
 
         request.params_mut().extend(handler.1.clone());
+        request.route_match = handler.2.clone();
 
         let mut service = service_builder.service(handler.handler());
 
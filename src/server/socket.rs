@@ -0,0 +1,205 @@
+//! Socket level configuration for the listening socket and every accepted connection.
+
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io;
+use tokio::net::TcpStream;
+
+/// The backlog used when none is configured, this matches what [`tokio::net::TcpListener::bind`] uses.
+const DEFAULT_BACKLOG: u32 = 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// TCP keepalive configuration for accepted connections.
+///
+/// Any value left as `None` keeps the operating system default.
+pub struct TcpKeepalive {
+    time: Option<Duration>,
+    interval: Option<Duration>,
+    retries: Option<u32>,
+}
+
+impl TcpKeepalive {
+    /// Creates a new keepalive configuration that only enables `SO_KEEPALIVE`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the idle time before the first keepalive probe is sent (`TCP_KEEPIDLE`).
+    pub fn time(mut self, time: Duration) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Sets the time between keepalive probes (`TCP_KEEPINTVL`).
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Sets the number of unanswered probes before the connection is dropped (`TCP_KEEPCNT`).
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    fn to_socket2(self) -> socket2::TcpKeepalive {
+        let mut keepalive = socket2::TcpKeepalive::new();
+
+        if let Some(time) = self.time {
+            keepalive = keepalive.with_time(time);
+        }
+
+        #[cfg(any(
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos",
+            windows
+        ))]
+        if let Some(interval) = self.interval {
+            keepalive = keepalive.with_interval(interval);
+        }
+
+        #[cfg(any(
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos"
+        ))]
+        if let Some(retries) = self.retries {
+            keepalive = keepalive.with_retries(retries);
+        }
+
+        keepalive
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Socket options for the server.
+///
+/// Options marked as listener options are only applied to sockets bound by the server itself,
+/// a listener passed in with [`HttpServerBuilder::listener`] is used as is.
+/// Connection options are applied to every accepted [`TcpStream`] regardless of where the listener came from.
+///
+/// # Examples
+/// ```
+/// # use std::time::Duration;
+/// # use tosic_http::server::socket::{SocketOptions, TcpKeepalive};
+/// let options = SocketOptions::new()
+///     .nodelay(true)
+///     .backlog(4096)
+///     .reuse_port(true)
+///     .keepalive(TcpKeepalive::new().time(Duration::from_secs(60)));
+/// ```
+///
+/// [`HttpServerBuilder::listener`]: crate::server::builder::HttpServerBuilder::listener
+pub struct SocketOptions {
+    nodelay: Option<bool>,
+    backlog: Option<u32>,
+    reuse_address: Option<bool>,
+    reuse_port: Option<bool>,
+    keepalive: Option<TcpKeepalive>,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
+}
+
+impl SocketOptions {
+    /// Creates a new set of options where everything is left at the default.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connection option: sets `TCP_NODELAY`, disabling Nagle's algorithm when `true`.
+    pub fn nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = Some(nodelay);
+        self
+    }
+
+    /// Listener option: sets the maximum length of the pending connection queue, defaults to `1024`.
+    pub fn backlog(mut self, backlog: u32) -> Self {
+        self.backlog = Some(backlog);
+        self
+    }
+
+    /// Listener option: sets `SO_REUSEADDR`, defaults to `true` on unix platforms.
+    pub fn reuse_address(mut self, reuse: bool) -> Self {
+        self.reuse_address = Some(reuse);
+        self
+    }
+
+    /// Listener option: sets `SO_REUSEPORT`, this is ignored on platforms that do not support it.
+    pub fn reuse_port(mut self, reuse: bool) -> Self {
+        self.reuse_port = Some(reuse);
+        self
+    }
+
+    /// Connection option: enables TCP keepalive with the given configuration.
+    pub fn keepalive(mut self, keepalive: TcpKeepalive) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    /// Listener and connection option: sets `SO_SNDBUF`.
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Listener and connection option: sets `SO_RCVBUF`.
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Creates a new non-blocking listener bound to `addr` with the listener options applied.
+    pub(crate) fn bind(&self, addr: SocketAddr) -> io::Result<std::net::TcpListener> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+
+        socket.set_reuse_address(self.reuse_address.unwrap_or(cfg!(unix)))?;
+
+        #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+        if let Some(reuse) = self.reuse_port {
+            socket.set_reuse_port(reuse)?;
+        }
+
+        self.apply_buffer_sizes(&socket)?;
+
+        socket.set_nonblocking(true)?;
+        socket.bind(&addr.into())?;
+
+        let backlog = self.backlog.unwrap_or(DEFAULT_BACKLOG);
+        socket.listen(backlog.try_into().unwrap_or(i32::MAX))?;
+
+        Ok(socket.into())
+    }
+
+    /// Applies the connection options to an accepted stream.
+    pub(crate) fn apply_to_stream(&self, stream: &TcpStream) -> io::Result<()> {
+        let socket = SockRef::from(stream);
+
+        if let Some(nodelay) = self.nodelay {
+            socket.set_nodelay(nodelay)?;
+        }
+
+        if let Some(keepalive) = self.keepalive {
+            socket.set_tcp_keepalive(&keepalive.to_socket2())?;
+        }
+
+        self.apply_buffer_sizes(&socket)
+    }
+
+    fn apply_buffer_sizes(&self, socket: &Socket) -> io::Result<()> {
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+
+        Ok(())
+    }
+}
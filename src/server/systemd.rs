@@ -0,0 +1,166 @@
+//! Support for systemd socket activation and readiness notifications.
+//!
+//! Listeners are taken over from the `LISTEN_PID`, `LISTEN_FDS` and `LISTEN_FDNAMES` environment variables
+//! as described in `sd_listen_fds(3)`, and state changes are sent to `NOTIFY_SOCKET` as described in `sd_notify(3)`.
+
+use socket2::{Domain, SockRef, Socket, Type};
+use std::env;
+use std::ffi::OsString;
+use std::os::fd::{BorrowedFd, FromRawFd, RawFd};
+use std::os::unix::net::UnixDatagram;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::io;
+
+/// The first file descriptor passed by systemd.
+const SD_LISTEN_FDS_START: RawFd = 3;
+
+/// Set once the listeners passed by systemd were taken, so they are never owned twice.
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Which of the listeners passed by systemd the server should use.
+pub(crate) enum SystemdListeners {
+    /// Use every listener passed.
+    All,
+    /// Only use listeners with one of these names in `LISTEN_FDNAMES`.
+    Named(Vec<String>),
+}
+
+impl SystemdListeners {
+    /// Takes the listeners passed by systemd to this process.
+    ///
+    /// The environment is only read, changing it while the runtime's threads may read it is not safe.
+    /// Child processes ignore the variables since `LISTEN_PID` names this process, and the listeners
+    /// are only taken by the first call in this process.
+    pub(crate) fn take(&self) -> io::Result<Vec<std::net::TcpListener>> {
+        let listen_pid = env::var("LISTEN_PID").ok();
+        let listen_fds = env::var("LISTEN_FDS").ok();
+        let listen_fdnames = env::var("LISTEN_FDNAMES").ok();
+
+        if listen_fds.is_none() || TAKEN.swap(true, Ordering::SeqCst) {
+            return Ok(Vec::new());
+        }
+
+        let fds = listen_fds_from(
+            listen_pid.as_deref(),
+            listen_fds.as_deref(),
+            listen_fdnames.as_deref(),
+            SD_LISTEN_FDS_START,
+        )?;
+
+        fds.into_iter()
+            .filter(|(name, _)| match self {
+                SystemdListeners::All => true,
+                SystemdListeners::Named(names) => names.iter().any(|wanted| wanted == name),
+            })
+            .map(|(_, fd)| tcp_listener_from_fd(fd))
+            .collect()
+    }
+}
+
+/// Parses the socket activation variables into a list of names and file descriptors.
+///
+/// Nothing is returned if the variables are missing or were meant for another process.
+pub(crate) fn listen_fds_from(
+    listen_pid: Option<&str>,
+    listen_fds: Option<&str>,
+    listen_fdnames: Option<&str>,
+    start: RawFd,
+) -> io::Result<Vec<(String, RawFd)>> {
+    let (Some(listen_pid), Some(listen_fds)) = (listen_pid, listen_fds) else {
+        return Ok(Vec::new());
+    };
+
+    let listen_pid: u32 = listen_pid
+        .trim()
+        .parse()
+        .map_err(|_| invalid_data("LISTEN_PID is not a valid pid"))?;
+
+    if listen_pid != std::process::id() {
+        return Ok(Vec::new());
+    }
+
+    let count: u16 = listen_fds
+        .trim()
+        .parse()
+        .map_err(|_| invalid_data("LISTEN_FDS is not a valid number"))?;
+
+    let mut names = listen_fdnames
+        .map(|names| names.split(':').map(str::to_string).collect::<Vec<_>>())
+        .unwrap_or_default();
+    names.resize(count as usize, "unknown".to_string());
+
+    Ok(names.into_iter().zip(start..).collect())
+}
+
+/// Takes ownership of `fd` and turns it into a [`std::net::TcpListener`].
+///
+/// The descriptor is checked to be an IPv4 or IPv6 stream socket before it is taken over.
+pub(crate) fn tcp_listener_from_fd(fd: RawFd) -> io::Result<std::net::TcpListener> {
+    // SAFETY: the file descriptor was passed to this process by systemd and is open for as long as
+    // the borrow is used.
+    let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+    let socket = SockRef::from(&borrowed);
+
+    if socket.r#type()? != Type::STREAM {
+        return Err(invalid_data(
+            "a file descriptor passed by systemd is not a stream socket",
+        ));
+    }
+
+    if !matches!(socket.domain()?, Domain::IPV4 | Domain::IPV6) {
+        return Err(invalid_data(
+            "a file descriptor passed by systemd is not a TCP socket",
+        ));
+    }
+
+    // SAFETY: the file descriptors are only taken once, see `TAKEN`.
+    let socket = unsafe { Socket::from_raw_fd(fd) };
+    socket.set_cloexec(true)?;
+
+    Ok(socket.into())
+}
+
+/// The socket readiness notifications are sent to.
+#[derive(Debug, Clone)]
+pub(crate) struct NotifySocket(OsString);
+
+impl NotifySocket {
+    /// Sends notifications to the socket at `path`, a leading `@` names an abstract socket.
+    pub(crate) fn new(path: impl Into<OsString>) -> Self {
+        Self(path.into())
+    }
+
+    /// Returns the socket in `NOTIFY_SOCKET`, if the process was started with one.
+    pub(crate) fn from_env() -> Option<Self> {
+        env::var_os("NOTIFY_SOCKET").map(Self::new)
+    }
+
+    /// Sends `state` to the socket.
+    pub(crate) fn notify(&self, state: &str) -> io::Result<()> {
+        let socket = UnixDatagram::unbound()?;
+        let path = self.0.to_string_lossy();
+
+        match path.strip_prefix('@') {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Some(name) => {
+                #[cfg(target_os = "android")]
+                use std::os::android::net::SocketAddrExt;
+                #[cfg(target_os = "linux")]
+                use std::os::linux::net::SocketAddrExt;
+
+                let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                socket.send_to_addr(state.as_bytes(), &addr)?;
+            }
+            _ => {
+                socket.send_to(state.as_bytes(), path.as_ref())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -3,13 +3,19 @@
 #![cfg(test)]
 
 use crate::body::BoxBody;
+use crate::error::ServerError;
 use crate::request::HttpRequest;
 use crate::server::builder::HttpServerBuilder;
+use crate::server::socket::{SocketOptions, TcpKeepalive};
 use crate::services::HttpService;
 use crate::traits::handler::Handler;
 use crate::traits::responder::Responder;
 use http::Method;
 use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
 
 async fn test_handler(_req: HttpRequest) -> impl Responder<Body = BoxBody> {
     "test response"
@@ -47,3 +53,1291 @@ async fn test() {
 
     let _server = server.unwrap();
 }
+
+/// Sends a `GET` request for `path` to `addr` and returns the raw response
+async fn get(addr: SocketAddr, path: &str) -> String {
+    send(
+        addr,
+        &format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path),
+    )
+    .await
+}
+
+/// Sends a raw request to `addr` and returns the raw response
+async fn send(addr: SocketAddr, request: &str) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn test_socket_options() {
+    let options = SocketOptions::new()
+        .nodelay(true)
+        .backlog(16)
+        .reuse_address(true)
+        .keepalive(
+            TcpKeepalive::new()
+                .time(Duration::from_secs(30))
+                .interval(Duration::from_secs(5))
+                .retries(3),
+        )
+        .send_buffer_size(64 * 1024)
+        .recv_buffer_size(64 * 1024);
+
+    let server = HttpServerBuilder::default()
+        .socket_options(options)
+        .service_method(Method::GET, "/", test_handler)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    let response = get(addr, "/").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.ends_with("test response"));
+}
+
+#[tokio::test]
+async fn test_existing_listener() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = HttpServerBuilder::<&str, _>::default()
+        .listener(listener)
+        .service_method(Method::GET, "/", test_handler)
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(server.local_addrs().unwrap(), vec![addr]);
+    tokio::spawn(server.serve());
+
+    let response = get(addr, "/").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+}
+
+#[tokio::test]
+async fn test_serve_with_shutdown() {
+    let server = HttpServerBuilder::default()
+        .service_method(Method::GET, "/", test_handler)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    let (shutdown, signal) = tokio::sync::oneshot::channel::<()>();
+    let serving = tokio::spawn(server.serve_with_shutdown(async {
+        let _ = signal.await;
+    }));
+
+    assert!(get(addr, "/").await.starts_with("HTTP/1.1 200 OK"));
+
+    shutdown.send(()).unwrap();
+    serving.await.unwrap().unwrap();
+
+    assert!(TcpStream::connect(addr).await.is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_systemd_listen_fds() {
+    use crate::server::systemd::listen_fds_from;
+
+    let pid = std::process::id().to_string();
+
+    let fds = listen_fds_from(Some(&pid), Some("2"), Some("http:admin"), 3).unwrap();
+    assert_eq!(fds, vec![("http".to_string(), 3), ("admin".to_string(), 4)]);
+
+    let fds = listen_fds_from(Some(&pid), Some("2"), None, 3).unwrap();
+    assert_eq!(
+        fds,
+        vec![("unknown".to_string(), 3), ("unknown".to_string(), 4)]
+    );
+
+    let other_pid = (std::process::id() + 1).to_string();
+    assert!(listen_fds_from(Some(&other_pid), Some("1"), None, 3)
+        .unwrap()
+        .is_empty());
+    assert!(listen_fds_from(None, None, None, 3).unwrap().is_empty());
+    assert!(listen_fds_from(Some(&pid), Some("many"), None, 3).is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_systemd_notify() {
+    use crate::server::systemd::NotifySocket;
+    use tokio::net::UnixDatagram;
+    use tokio::time::timeout;
+
+    let path = std::env::temp_dir().join(format!("tosic-http-notify-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let notify_socket = UnixDatagram::bind(&path).unwrap();
+
+    let mut server = HttpServerBuilder::default()
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+    server.notify_socket = Some(NotifySocket::new(&path));
+
+    let (shutdown, signal) = tokio::sync::oneshot::channel::<()>();
+    let serving = tokio::spawn(server.serve_with_shutdown(async {
+        let _ = signal.await;
+    }));
+
+    let mut buf = [0; 64];
+    let n = timeout(Duration::from_secs(5), notify_socket.recv(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(&buf[..n], b"READY=1");
+
+    shutdown.send(()).unwrap();
+    serving.await.unwrap().unwrap();
+
+    let n = timeout(Duration::from_secs(5), notify_socket.recv(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(&buf[..n], b"STOPPING=1");
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_systemd_rejects_non_tcp_sockets() {
+    use crate::server::systemd::tcp_listener_from_fd;
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::os::unix::net::UnixStream;
+
+    let (mut unix, mut other) = UnixStream::pair().unwrap();
+    assert!(tcp_listener_from_fd(unix.as_raw_fd()).is_err());
+
+    // the rejected socket is still open and owned by the caller
+    unix.write_all(b"x").unwrap();
+    let mut buf = [0; 1];
+    other.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"x");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_upgrade_listener_handoff() {
+    use crate::server::upgrade::{recv_fds, send_fds};
+    use std::os::fd::OwnedFd;
+    use std::os::unix::net::UnixStream;
+
+    let first = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let second = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addrs = vec![first.local_addr().unwrap(), second.local_addr().unwrap()];
+
+    let (old, new) = UnixStream::pair().unwrap();
+    send_fds(&old, &[OwnedFd::from(first), OwnedFd::from(second)]).unwrap();
+
+    let listeners = recv_fds(&new)
+        .unwrap()
+        .into_iter()
+        .map(std::net::TcpListener::from)
+        .collect::<Vec<_>>();
+
+    let mut builder = HttpServerBuilder::<&str, _>::default();
+    for listener in listeners {
+        builder = builder.listener(listener);
+    }
+
+    let server = builder
+        .service_method(Method::GET, "/", test_handler)
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(server.local_addrs().unwrap(), addrs);
+    tokio::spawn(server.serve());
+
+    for addr in addrs {
+        assert!(get(addr, "/").await.starts_with("HTTP/1.1 200 OK"));
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_hot_upgrade_handoff() {
+    use crate::server::upgrade::handoff_to;
+    use std::os::fd::OwnedFd;
+    use tokio::process::Command;
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // the new process is this test binary running only `upgraded_process`
+    let mut command = Command::new(std::env::current_exe().unwrap());
+    command
+        .args(["--exact", "server::test::upgraded_process"])
+        .stdout(std::process::Stdio::null());
+
+    let pid = handoff_to(command, &[OwnedFd::from(listener)])
+        .await
+        .unwrap();
+    assert_ne!(pid, std::process::id());
+
+    assert!(get(addr, "/").await.ends_with("upgraded"));
+}
+
+/// The new process started by `test_hot_upgrade_handoff`, it does nothing when run on its own
+#[cfg(unix)]
+#[tokio::test]
+async fn upgraded_process() {
+    use crate::server::upgrade::UPGRADE_FD_ENV;
+    use tokio::signal::unix::SignalKind;
+    use tokio::sync::Notify;
+
+    static DONE: Notify = Notify::const_new();
+
+    async fn upgraded() -> &'static str {
+        DONE.notify_one();
+        "upgraded"
+    }
+
+    if std::env::var_os(UPGRADE_FD_ENV).is_none() {
+        return;
+    }
+
+    let server = HttpServerBuilder::<&str, _>::default()
+        .hot_upgrade(SignalKind::user_defined2())
+        .service_method(Method::GET, "/", upgraded)
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(server.local_addrs().unwrap().len(), 1);
+
+    server.serve_with_shutdown(DONE.notified()).await.unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_upgrade_fd_only_for_children() {
+    use crate::server::upgrade::inherited_fd;
+
+    assert_eq!(inherited_fd("42:7", 42).unwrap(), Some(7));
+    assert_eq!(inherited_fd("42:7", 43).unwrap(), None);
+    assert!(inherited_fd("/tmp/upgrade.sock", 42).is_err());
+    assert!(inherited_fd("42:fd", 42).is_err());
+}
+
+#[tokio::test]
+async fn test_connection_hooks() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Clone)]
+    struct Tag(String);
+
+    async fn tagged(req: HttpRequest) -> impl Responder<Body = BoxBody> {
+        let tag = req
+            .connection_extensions()
+            .and_then(|extensions| extensions.get::<Tag>())
+            .map(|tag| tag.0.clone())
+            .unwrap_or_default();
+
+        format!("{} {}", req.connection().unwrap().id(), tag)
+    }
+
+    let disconnected = Arc::new(AtomicUsize::new(0));
+    let counter = disconnected.clone();
+
+    let server = HttpServerBuilder::default()
+        .on_connect(|meta, extensions| {
+            extensions.insert(Tag(format!("peer={}", meta.peer_addr().ip())));
+        })
+        .on_disconnect(move |_, extensions| {
+            assert!(extensions.get::<Tag>().is_some());
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .service_method(Method::GET, "/", tagged)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/").await.ends_with("0 peer=127.0.0.1"));
+    assert!(get(addr, "/").await.ends_with("1 peer=127.0.0.1"));
+
+    for _ in 0..50 {
+        if disconnected.load(Ordering::SeqCst) == 2 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    panic!("on_disconnect did not run for every connection");
+}
+
+#[tokio::test]
+async fn test_panicking_connection_hooks() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    async fn ok() -> impl Responder<Body = BoxBody> {
+        "ok"
+    }
+
+    async fn panics() -> impl Responder<Body = BoxBody> {
+        panic!("handler failed");
+        #[allow(unreachable_code)]
+        ""
+    }
+
+    let disconnected = Arc::new(AtomicUsize::new(0));
+    let counter = disconnected.clone();
+
+    let server = HttpServerBuilder::default()
+        .on_connect(|meta, _| {
+            if meta.id() == 0 {
+                panic!("connect hook failed");
+            }
+        })
+        .on_disconnect(|_, _| panic!("disconnect hook failed"))
+        .on_disconnect(move |_, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .service_method(Method::GET, "/", ok)
+        .service_method(Method::GET, "/panic", panics)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    // the first connection is closed by its hook, the server keeps accepting
+    let mut closed = String::new();
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.read_to_string(&mut closed).await.unwrap();
+    assert!(closed.is_empty());
+
+    assert!(get(addr, "/").await.ends_with("ok"));
+    assert_eq!(get(addr, "/panic").await, "");
+
+    for _ in 0..50 {
+        if disconnected.load(Ordering::SeqCst) == 2 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    panic!("on_disconnect did not run for the connection whose handler panicked");
+}
+
+#[tokio::test]
+async fn test_startup_failure_aborts_build() {
+    use crate::error::ServerError;
+    use crate::state::State;
+
+    let result = HttpServerBuilder::default()
+        .on_startup(|_: State| async { Err::<(), _>("cache warmup failed") })
+        .bind("127.0.0.1:0")
+        .build()
+        .await;
+
+    match result {
+        Err(ServerError::Startup(err)) => assert_eq!(err.to_string(), "cache warmup failed"),
+        _ => panic!("expected the startup hook to abort the build"),
+    }
+}
+
+#[tokio::test]
+async fn test_lifecycle_hooks_and_background_tasks() {
+    use crate::server::lifecycle::CancellationToken;
+    use crate::state::State;
+    use std::sync::{Arc, Mutex};
+
+    struct Events(Mutex<Vec<&'static str>>);
+
+    impl Events {
+        fn push(&self, event: &'static str) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    let events = Arc::new(Events(Mutex::new(Vec::new())));
+    let (started, task_started) = tokio::sync::oneshot::channel::<()>();
+    let started = Mutex::new(Some(started));
+
+    let server = HttpServerBuilder::default()
+        .app_state(events.clone())
+        .on_startup(|state: State| async move {
+            state.get::<Arc<Events>>().unwrap().push("startup");
+            Ok::<_, &str>(())
+        })
+        .background_task(move |state: State, cancel: CancellationToken| {
+            let started = started.lock().unwrap().take();
+
+            async move {
+                if let Some(started) = started {
+                    let _ = started.send(());
+                }
+                cancel.cancelled().await;
+                state.get::<Arc<Events>>().unwrap().push("task stopped");
+            }
+        })
+        .on_shutdown(|state: State| async move {
+            state.get::<Arc<Events>>().unwrap().push("shutdown");
+            Ok::<_, &str>(())
+        })
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(*events.0.lock().unwrap(), vec!["startup"]);
+
+    let (shutdown, signal) = tokio::sync::oneshot::channel::<()>();
+    let serving = tokio::spawn(server.serve_with_shutdown(async {
+        let _ = signal.await;
+    }));
+
+    task_started.await.unwrap();
+    shutdown.send(()).unwrap();
+    serving.await.unwrap().unwrap();
+
+    assert_eq!(
+        *events.0.lock().unwrap(),
+        vec!["startup", "task stopped", "shutdown"]
+    );
+}
+
+#[tokio::test]
+async fn test_frozen_and_mutable_state() {
+    use crate::extractors::Data;
+    use crate::state::State;
+    use tokio::sync::RwLock;
+
+    struct Greeting {
+        text: &'static str,
+    }
+    struct Counter(u64);
+
+    async fn count(greeting: Data<Greeting>, counter: Data<RwLock<Counter>>) -> String {
+        let mut counter = counter.write().await;
+        counter.0 += 1;
+        format!("{} {}", greeting.text, counter.0)
+    }
+
+    let mut state = State::new();
+    state.insert(Greeting { text: "hello" });
+    let frozen = state.clone();
+    state.insert(Counter(0));
+    assert!(frozen.get::<Counter>().is_none());
+    assert_eq!(state.get::<Greeting>().unwrap().text, "hello");
+
+    let server = HttpServerBuilder::default()
+        .app_state(Greeting { text: "hello" })
+        .app_state_mut(Counter(0))
+        .service_method(Method::GET, "/", count)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/").await.ends_with("hello 1"));
+    assert!(get(addr, "/").await.ends_with("hello 2"));
+}
+
+#[tokio::test]
+async fn test_reloadable_state() {
+    use crate::extractors::Snapshot;
+    use crate::state::Reloadable;
+
+    struct Upstream {
+        url: &'static str,
+    }
+
+    async fn upstream(upstream: Snapshot<Upstream>) -> &'static str {
+        upstream.url
+    }
+
+    let reloadable = Reloadable::new(Upstream { url: "first" });
+    let mut changes = reloadable.subscribe();
+
+    let server = HttpServerBuilder::default()
+        .app_state_reloadable(reloadable.clone())
+        .service_method(Method::GET, "/", upstream)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/").await.ends_with("first"));
+
+    reloadable.store(Upstream { url: "second" });
+    changes.changed().await.unwrap();
+    assert_eq!(changes.borrow_and_update().url, "second");
+
+    assert!(get(addr, "/").await.ends_with("second"));
+}
+
+#[tokio::test]
+async fn test_trait_object_and_derived_state() {
+    use crate::extractors::Data;
+    use crate::traits::from_ref::FromRef;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    static DERIVED: AtomicUsize = AtomicUsize::new(0);
+
+    trait Greeter: Send + Sync {
+        fn greet(&self) -> String;
+    }
+
+    struct English;
+
+    impl Greeter for English {
+        fn greet(&self) -> String {
+            "hello".to_string()
+        }
+    }
+
+    struct Config {
+        name: &'static str,
+    }
+
+    struct Name(&'static str);
+
+    impl FromRef<Config> for Name {
+        fn from_ref(config: &Config) -> Self {
+            DERIVED.fetch_add(1, Ordering::SeqCst);
+            Name(config.name)
+        }
+    }
+
+    async fn greet(greeter: Data<dyn Greeter>, name: Data<Name>) -> String {
+        format!("{} {}", greeter.greet(), name.0 .0)
+    }
+
+    let server = HttpServerBuilder::default()
+        .app_state_arc::<dyn Greeter>(Arc::new(English))
+        .app_state(Config { name: "world" })
+        .app_state_from_ref::<Config, Name>()
+        .service_method(Method::GET, "/", greet)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/").await.ends_with("hello world"));
+    assert!(get(addr, "/").await.ends_with("hello world"));
+
+    // the derived value is built once when the server is built, not on every extraction
+    assert_eq!(DERIVED.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_state_factories() {
+    use crate::error::ServerError;
+    use crate::state::State;
+
+    struct Pool(u32);
+
+    let pool = HttpServerBuilder::default()
+        .app_state_factory(|| async { Ok::<_, &str>(Pool(4)) })
+        .on_startup(|state: State| async move {
+            let pool = state.get::<Pool>().ok_or("the pool was not created")?;
+            assert_eq!(pool.0, 4);
+            Ok::<_, &str>(())
+        })
+        .bind("127.0.0.1:0")
+        .build()
+        .await;
+
+    assert!(pool.is_ok());
+
+    let result = HttpServerBuilder::default()
+        .app_state_factory(|| async { Err::<Pool, _>("could not connect") })
+        .bind("127.0.0.1:0")
+        .build()
+        .await;
+
+    match result {
+        Err(ServerError::StateFactory(err)) => assert_eq!(err.to_string(), "could not connect"),
+        _ => panic!("expected the state factory to abort the build"),
+    }
+}
+
+#[tokio::test]
+async fn test_scopes() {
+    use crate::extractors::{Data, ReqData};
+    use crate::resource::get as route_get;
+    use crate::scope::Scope;
+    use tower::util::MapRequestLayer;
+
+    #[derive(Clone)]
+    struct Admin;
+
+    async fn area(area: Data<&'static str>) -> String {
+        area.to_string()
+    }
+
+    async fn dashboard(_: ReqData<Admin>, area: Data<&'static str>) -> String {
+        format!("dashboard {}", *area)
+    }
+
+    let admin = Scope::new("/admin")
+        .app_state("admin")
+        .wrap(MapRequestLayer::new(
+            |(mut req, payload): (HttpRequest, crate::request::HttpPayload)| {
+                req.extensions_mut().insert(Admin);
+                (req, payload)
+            },
+        ))
+        .route(route_get("/", dashboard))
+        .scope(Scope::new("/settings/").service_method(Method::GET, "/area", area));
+
+    let server = HttpServerBuilder::default()
+        .app_state("public")
+        .scope(admin)
+        .scope(Scope::new("/public").service_method(Method::GET, "/", area))
+        .service_method(Method::GET, "/dashboard", dashboard)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/admin").await.ends_with("dashboard admin"));
+    assert!(get(addr, "/admin/settings/area").await.ends_with("admin"));
+    assert!(get(addr, "/public").await.ends_with("public"));
+    assert!(get(addr, "/dashboard")
+        .await
+        .starts_with("HTTP/1.1 500 Internal Server Error"));
+}
+
+#[tokio::test]
+async fn test_route_layers() {
+    use crate::resource::get as route_get;
+    use crate::response::HttpResponse;
+    use tower::util::MapResponseLayer;
+
+    fn tag(value: &'static str) -> impl Fn(HttpResponse) -> HttpResponse + Clone {
+        move |mut response: HttpResponse| {
+            response
+                .headers_mut()
+                .append("x-layer", value.parse().unwrap());
+            response
+        }
+    }
+
+    let server = HttpServerBuilder::default()
+        .route(
+            route_get("/layered", test_handler)
+                .wrap(MapResponseLayer::new(tag("outer")))
+                .wrap(MapResponseLayer::new(tag("inner"))),
+        )
+        .service_method(Method::GET, "/plain", test_handler)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    let layered = get(addr, "/layered").await.to_lowercase();
+    let inner = layered.find("x-layer: inner").unwrap();
+    let outer = layered.find("x-layer: outer").unwrap();
+    assert!(inner < outer);
+
+    assert!(!get(addr, "/plain").await.to_lowercase().contains("x-layer"));
+}
+
+#[tokio::test]
+async fn test_guards() {
+    use crate::guard::{ContentType, Header, Host};
+    use crate::resource::{get as route_get, post as route_post};
+    use crate::scope::Scope;
+
+    async fn v1() -> &'static str {
+        "v1"
+    }
+
+    async fn v2() -> &'static str {
+        "v2"
+    }
+
+    async fn json() -> &'static str {
+        "json"
+    }
+
+    async fn blog() -> &'static str {
+        "blog"
+    }
+
+    let server = HttpServerBuilder::default()
+        .route(route_get("/users", v1))
+        .route(route_get("/users", v2).guard(Header::new("api-version", "2")))
+        .route(route_post("/upload", json).guard(ContentType::new(mime::APPLICATION_JSON)))
+        .scope(
+            Scope::new("/")
+                .guard(Host::new("blog.example.com"))
+                .service_method(Method::GET, "/home", blog),
+        )
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/users").await.ends_with("v1"));
+    assert!(send(addr, "GET /users HTTP/1.1\r\napi-version: 2\r\n\r\n")
+        .await
+        .ends_with("v2"));
+
+    assert!(send(
+        addr,
+        "POST /upload HTTP/1.1\r\nContent-Type: application/json; charset=utf-8\r\n\r\n"
+    )
+    .await
+    .ends_with("json"));
+    assert!(send(
+        addr,
+        "POST /upload HTTP/1.1\r\nContent-Type: text/plain\r\n\r\n"
+    )
+    .await
+    .starts_with("HTTP/1.1 404 Not Found"));
+
+    assert!(send(
+        addr,
+        "GET /home HTTP/1.1\r\nHost: BLOG.example.com:8080\r\n\r\n"
+    )
+    .await
+    .ends_with("blog"));
+    assert!(get(addr, "/home")
+        .await
+        .starts_with("HTTP/1.1 404 Not Found"));
+}
+
+#[tokio::test]
+async fn test_guards_fall_through_to_other_routes() {
+    use crate::extractors::Path;
+    use crate::guard::{Header, Host};
+    use crate::resource::get as route_get;
+    use crate::scope::Scope;
+
+    async fn me() -> &'static str {
+        "me"
+    }
+
+    async fn user(id: Path<String>) -> String {
+        format!("user {}", id.into_inner())
+    }
+
+    async fn local() -> &'static str {
+        "local"
+    }
+
+    let server = HttpServerBuilder::default()
+        .route(route_get("/users/me", me).guard(Header::new("authorization", "token")))
+        .service_method(Method::GET, "/users/{id}", user)
+        .scope(
+            Scope::new("/")
+                .guard(Host::new("[::1]:8080"))
+                .service_method(Method::GET, "/local", local),
+        )
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(send(
+        addr,
+        "GET /users/me HTTP/1.1\r\nauthorization: token\r\n\r\n"
+    )
+    .await
+    .ends_with("me"));
+    // the guard of `/users/me` fails, so the request is handled by `/users/{id}` instead of a 404
+    assert!(get(addr, "/users/me").await.ends_with("user me"));
+
+    assert!(
+        send(addr, "GET /local HTTP/1.1\r\nHost: [::1]:8080\r\n\r\n")
+            .await
+            .ends_with("local")
+    );
+    assert!(
+        send(addr, "GET /local HTTP/1.1\r\nHost: [::1]:9090\r\n\r\n")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found")
+    );
+}
+
+#[tokio::test]
+async fn test_ambiguous_routes() {
+    async fn handler() -> &'static str {
+        "ok"
+    }
+
+    let result = HttpServerBuilder::default()
+        .service_method(Method::GET, "/users/{id}", handler)
+        .service_method(Method::GET, "/users/{name}", handler)
+        .service_method(Method::POST, "/users/{name}", handler)
+        .bind("127.0.0.1:0")
+        .build()
+        .await;
+
+    let Err(ServerError::RouteConflicts(conflicts)) = result else {
+        panic!("ambiguous routes should fail the build");
+    };
+
+    assert_eq!(conflicts.0.len(), 1);
+    assert_eq!(
+        conflicts.to_string(),
+        "1 conflicting route(s):\n  GET /users/{name} is ambiguous with GET /users/{id}"
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_route_fails_the_build() {
+    async fn handler() -> &'static str {
+        "ok"
+    }
+
+    let result = HttpServerBuilder::default()
+        .service_method(Method::GET, "/users/{id:[0-9}", handler)
+        .bind("127.0.0.1:0")
+        .build()
+        .await;
+
+    let Err(ServerError::InvalidRoute(invalid)) = result else {
+        panic!("a route with an invalid constraint should fail the build");
+    };
+
+    assert_eq!(invalid.route, "/users/{id:[0-9}");
+
+    let result = HttpServerBuilder::default()
+        .route(crate::resource::get("/a/{id}/{id}", handler))
+        .bind("127.0.0.1:0")
+        .build()
+        .await;
+
+    let Err(ServerError::InvalidRoute(invalid)) = result else {
+        panic!("a route with a duplicate parameter should fail the build");
+    };
+
+    assert_eq!(invalid.reason, "duplicate parameter `id`");
+}
+
+#[tokio::test]
+async fn test_duplicate_routes_and_route_table() {
+    use crate::error::ConflictKind;
+    use crate::guard::Host;
+    use crate::resource::get as route_get;
+
+    async fn handler() -> &'static str {
+        "ok"
+    }
+
+    let result = HttpServerBuilder::default()
+        .service_method(Method::GET, "/users", handler)
+        .service_method(Method::GET, "/users", handler)
+        .route(route_get("/posts", handler).get(handler))
+        .bind("127.0.0.1:0")
+        .build()
+        .await;
+
+    let Err(ServerError::RouteConflicts(conflicts)) = result else {
+        panic!("duplicate routes should fail the build");
+    };
+
+    assert_eq!(conflicts.0.len(), 2);
+    assert!(conflicts
+        .0
+        .iter()
+        .all(|conflict| conflict.kind == ConflictKind::Duplicate));
+    assert_eq!(
+        conflicts.0[1].to_string(),
+        "GET /users is already registered as GET /users"
+    );
+
+    let result = HttpServerBuilder::default()
+        .route(route_get("/admin", handler).guard(Host::new("admin.example.com")))
+        .route(route_get("/admin", handler).guard(Host::new("ADMIN.example.com")))
+        .route(route_get("/admin", handler).guard(Host::new("example.com")))
+        .bind("127.0.0.1:0")
+        .build()
+        .await;
+
+    let Err(ServerError::RouteConflicts(conflicts)) = result else {
+        panic!("routes with equal guards should fail the build");
+    };
+
+    assert_eq!(conflicts.0.len(), 1);
+    assert_eq!(conflicts.0[0].kind, ConflictKind::Duplicate);
+
+    let server = HttpServerBuilder::default()
+        .service_method(Method::GET, "/users/{id}", handler)
+        .service_method(Method::DELETE, "/users/{id}", handler)
+        .service_method(Method::GET, "/", handler)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        server.routes(),
+        vec![
+            (Method::GET, "/".to_string()),
+            (Method::DELETE, "/users/{id}".to_string()),
+            (Method::GET, "/users/{id}".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_path_normalization() {
+    use crate::extractors::Path;
+    use crate::scope::Scope;
+    use crate::server::{PathNormalization, TrailingSlash};
+
+    async fn file(name: Path<String>) -> String {
+        name.into_inner()
+    }
+
+    async fn users() -> &'static str {
+        "users"
+    }
+
+    let server = HttpServerBuilder::default()
+        .service_method(Method::GET, "/files/{name}", file)
+        .service_method(Method::GET, "/users", users)
+        .service_method(Method::GET, "/docs/", users)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/files/a%2Fb%20c").await.ends_with("a/b c"));
+    assert!(get(addr, "/files/caf%C3%A9").await.ends_with("café"));
+    assert!(get(addr, "//users/./../users/").await.ends_with("users"));
+    // encoded dot segments are resolved too, they never reach a parameter as `..`
+    assert!(get(addr, "/files/%2e%2e/users").await.ends_with("users"));
+    assert!(get(addr, "/files/%2E")
+        .await
+        .starts_with("HTTP/1.1 404 Not Found"));
+    // a route declared with a trailing slash matches with and without it by default
+    assert!(get(addr, "/docs").await.ends_with("users"));
+    assert!(get(addr, "/docs/").await.ends_with("users"));
+
+    let server = HttpServerBuilder::default()
+        .service_method(Method::GET, "/static/{*path}", file)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/static/css/app.css")
+        .await
+        .ends_with("css/app.css"));
+    // an encoded slash stays encoded in a catch-all, so it can't add a segment
+    assert!(get(addr, "/static/%2e%2e%2F%2e%2e%2Fetc%2Fpasswd")
+        .await
+        .ends_with("..%2F..%2Fetc%2Fpasswd"));
+    // an encoded percent sign is encoded again, so it can't be read as an encoded slash
+    assert!(get(addr, "/static/a%252Fb").await.ends_with("\r\na%252Fb"));
+    assert!(get(addr, "/static/a%2Fb").await.ends_with("\r\na%2Fb"));
+    assert!(get(addr, "/static/100%25").await.ends_with("\r\n100%25"));
+    assert!(get(addr, "/static/a/%2e%2e/%2e%2e/%2e%2e/etc/passwd")
+        .await
+        .starts_with("HTTP/1.1 404 Not Found"));
+
+    let server = HttpServerBuilder::default()
+        .service_method(Method::GET, "/users", users)
+        .service_method(Method::GET, "/a/b", users)
+        .service_method(Method::GET, "/{name}", file)
+        .path_normalization(PathNormalization::new().trailing_slash(TrailingSlash::Redirect))
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    let response = get(addr, "/users/?page=2").await;
+    assert!(response.starts_with("HTTP/1.1 308 Permanent Redirect"));
+    assert!(response.to_lowercase().contains("location: /users?page=2"));
+    assert!(get(addr, "/users").await.ends_with("users"));
+    // the location is built from the normalized path, so it can't point at another host
+    let response = get(addr, "//evil.com/").await;
+    assert!(response.starts_with("HTTP/1.1 308 Permanent Redirect"));
+    assert!(response.to_lowercase().contains("location: /evil.com\r\n"));
+    let response = get(addr, "/a/./b/").await;
+    assert!(response.to_lowercase().contains("location: /a/b\r\n"));
+    let response = get(addr, "/a%20b/").await;
+    assert!(response.to_lowercase().contains("location: /a%20b\r\n"));
+
+    let server = HttpServerBuilder::default()
+        .service_method(Method::GET, "/users", users)
+        .scope(Scope::new("/admin").service_method(Method::GET, "/", users))
+        .path_normalization(
+            PathNormalization::new()
+                .merge_slashes(false)
+                .trailing_slash(TrailingSlash::Strict),
+        )
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/users").await.ends_with("users"));
+    assert!(get(addr, "/users/")
+        .await
+        .starts_with("HTTP/1.1 404 Not Found"));
+    assert!(get(addr, "//users")
+        .await
+        .starts_with("HTTP/1.1 404 Not Found"));
+    // the scope route is joined to `/admin/`, so only the path with the trailing slash matches it
+    assert!(get(addr, "/admin/").await.ends_with("users"));
+    assert!(get(addr, "/admin")
+        .await
+        .starts_with("HTTP/1.1 404 Not Found"));
+}
+
+#[tokio::test]
+async fn test_match_pattern() {
+    use crate::request::{HttpPayload, RouteMatch};
+    use std::sync::{Arc, Mutex};
+    use tower::util::MapRequestLayer;
+
+    async fn user(req: HttpRequest) -> String {
+        req.match_pattern().unwrap_or_default().to_string()
+    }
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let record = {
+        let seen = seen.clone();
+        move |(req, payload): (HttpRequest, HttpPayload)| {
+            seen.lock().unwrap().push(req.route_match().clone());
+            (req, payload)
+        }
+    };
+
+    let server = HttpServerBuilder::default()
+        .service_method(Method::GET, "/users/{id}", user)
+        .wrap(MapRequestLayer::new(record))
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/users/7").await.ends_with("/users/{id}"));
+    assert!(get(addr, "/missing")
+        .await
+        .starts_with("HTTP/1.1 404 Not Found"));
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen[0].pattern(), Some("/users/{id}"));
+    assert_eq!(seen[0].name(), None);
+    assert_eq!(seen[1], RouteMatch::NotFound);
+    assert!(seen[1].is_not_found());
+}
+
+#[tokio::test]
+async fn test_url_for() {
+    use crate::error::{ConflictKind, UrlGenerationError};
+    use crate::resource::get as route_get;
+
+    async fn handler() -> &'static str {
+        "ok"
+    }
+
+    async fn links(req: HttpRequest) -> String {
+        assert_eq!(
+            req.url_for("file", [("user", "a b"), ("path", "docs/ü.txt")]),
+            Ok("/users/a%20b/files/docs/%C3%BC.txt".to_string())
+        );
+        assert_eq!(
+            req.url_for("post", [("id", "x")]),
+            Err(UrlGenerationError::InvalidParameter {
+                name: "id".to_string(),
+                value: "x".to_string(),
+                constraint: "int".to_string(),
+            })
+        );
+        assert_eq!(
+            req.url_for("post", Vec::<(&str, &str)>::new()),
+            Err(UrlGenerationError::MissingParameter("id".to_string()))
+        );
+        assert_eq!(
+            req.url_for("post", [("id", "1"), ("page", "2")]),
+            Err(UrlGenerationError::UnexpectedParameter("page".to_string()))
+        );
+        assert_eq!(
+            req.url_for("missing", [("id", 1)]),
+            Err(UrlGenerationError::UnknownRoute("missing".to_string()))
+        );
+
+        req.url_for("post", [("id", 7)]).unwrap()
+    }
+
+    let server = HttpServerBuilder::default()
+        .route(route_get("/users/{user}/files/{*path}", handler).name("file"))
+        .route(route_get("/posts/{id:int}", handler).name("post"))
+        .service_method(Method::GET, "/links", links)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/links").await.ends_with("/posts/7"));
+
+    let result = HttpServerBuilder::default()
+        .route(route_get("/a", handler).name("same"))
+        .route(route_get("/b", handler).name("same"))
+        .bind("127.0.0.1:0")
+        .build()
+        .await;
+
+    let Err(ServerError::RouteConflicts(conflicts)) = result else {
+        panic!("routes with the same name should fail the build");
+    };
+
+    assert_eq!(conflicts.0[0].kind, ConflictKind::DuplicateName);
+    assert_eq!(conflicts.0[0].to_string(), "GET /b has the same name as /a");
+}
+
+#[tokio::test]
+async fn test_names_of_guarded_endpoints() {
+    use crate::guard::Header;
+    use crate::resource::get as route_get;
+
+    async fn named(req: HttpRequest) -> String {
+        let name = req.match_name().unwrap_or_default();
+
+        format!(
+            "{} {} {}",
+            name,
+            req.url_for("v1", [("id", 1)]).unwrap(),
+            req.url_for("v2", [("id", 2)]).unwrap()
+        )
+    }
+
+    let server = HttpServerBuilder::default()
+        .route(route_get("/items/{id}", named).name("v1"))
+        .route(
+            route_get("/items/{id}", named)
+                .guard(Header::new("api-version", "2"))
+                .name("v2"),
+        )
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/items/1")
+        .await
+        .ends_with("v1 /items/1 /items/2"));
+    assert!(
+        send(addr, "GET /items/1 HTTP/1.1\r\napi-version: 2\r\n\r\n")
+            .await
+            .ends_with("v2 /items/1 /items/2")
+    );
+}
+
+#[tokio::test]
+async fn test_multi_method_service() {
+    use crate::scope::Scope;
+
+    struct Items;
+
+    impl Handler<(HttpRequest,)> for Items {
+        type Output = String;
+        type Future = std::future::Ready<String>;
+
+        fn call(&self, (req,): (HttpRequest,)) -> Self::Future {
+            std::future::ready(req.method().to_string())
+        }
+    }
+
+    impl HttpService<(HttpRequest,)> for Items {
+        const METHODS: &'static [Method] = &[Method::GET, Method::POST];
+        const PATH: &'static str = "/items";
+    }
+
+    let server = HttpServerBuilder::default()
+        .service(Items)
+        .scope(Scope::new("/v2").service(Items))
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        server.routes(),
+        vec![
+            (Method::GET, "/items".to_string()),
+            (Method::POST, "/items".to_string()),
+            (Method::GET, "/v2/items".to_string()),
+            (Method::POST, "/v2/items".to_string()),
+        ]
+    );
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+
+    assert!(get(addr, "/items").await.ends_with("GET"));
+    assert!(
+        send(addr, "POST /v2/items HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .ends_with("POST")
+    );
+    assert!(send(addr, "PUT /items HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .starts_with("HTTP/1.1 404"));
+}
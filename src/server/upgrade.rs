@@ -0,0 +1,343 @@
+//! Zero-downtime binary upgrades by handing the listening sockets over to a new process.
+//!
+//! When the configured signal is received the running server spawns a new copy of the current executable
+//! with the same arguments. It inherits one end of a unix socket pair, which [`UPGRADE_FD_ENV`] names
+//! together with the pid of the old process. The new process takes the listening sockets from it while
+//! building its server and acknowledges once it has started accepting connections. Only then does the old
+//! process stop accepting and drain its open connections, so at least one process is accepting connections
+//! at all times.
+//!
+//! The socket pair is never reachable through the file system, so no other process can receive the
+//! listeners or acknowledge an upgrade in place of the new process.
+
+use socket2::{Domain, SockRef, Type};
+use std::env;
+use std::os::fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::io::{self, AsyncReadExt};
+use tokio::process::Command;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::time::timeout;
+use tracing::{error, info};
+
+/// The environment variable naming the inherited handoff socket of the new process as `<old pid>:<fd>`.
+pub(crate) const UPGRADE_FD_ENV: &str = "TOSIC_HTTP_UPGRADE_FD";
+
+/// How long the new process has to take over the listeners before the upgrade is aborted.
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The most listeners that can be handed over in a single upgrade.
+const MAX_LISTENERS: usize = 64;
+
+/// Set once the listeners were received, so a second server in this process does not take them again.
+static RECEIVED: AtomicBool = AtomicBool::new(false);
+
+/// Sent by the new process once it is accepting connections.
+const READY: u8 = 1;
+
+/// Waits for `kind` and hands the listeners over to a new process.
+///
+/// Failed upgrades are logged and the server keeps waiting for the next signal,
+/// this only returns once a new process has taken over.
+pub(crate) async fn watch(kind: SignalKind, listeners: Vec<OwnedFd>) -> io::Result<u32> {
+    let mut signal = signal(kind)?;
+
+    loop {
+        if signal.recv().await.is_none() {
+            return Err(io::Error::other("the upgrade signal stream was closed"));
+        }
+
+        info!("Received upgrade signal, starting a new process");
+
+        match handoff(&listeners).await {
+            Ok(pid) => return Ok(pid),
+            Err(err) => error!("Failed to upgrade to a new process: {}", err),
+        }
+    }
+}
+
+/// Spawns a new copy of the current executable and hands `listeners` over to it.
+async fn handoff(listeners: &[OwnedFd]) -> io::Result<u32> {
+    let mut command = Command::new(env::current_exe()?);
+    command.args(env::args_os().skip(1));
+
+    handoff_to(command, listeners).await
+}
+
+/// Starts `command` as the new process and hands `listeners` over to it.
+///
+/// Returns the pid of the new process once it acknowledged the upgrade.
+pub(crate) async fn handoff_to(mut command: Command, listeners: &[OwnedFd]) -> io::Result<u32> {
+    let (stream, inherited) = UnixStream::pair()?;
+    let inherited_fd = inherited.as_raw_fd();
+
+    command.env(
+        UPGRADE_FD_ENV,
+        format!("{}:{}", std::process::id(), inherited_fd),
+    );
+
+    // SAFETY: the closure only calls fcntl, which is async-signal-safe. Clearing close-on-exec in the
+    // child keeps the socket from leaking into processes spawned by other threads.
+    unsafe {
+        command.pre_exec(move || set_inheritable(inherited_fd));
+    }
+
+    let mut child = command.spawn()?;
+    drop(inherited);
+
+    let pid = child.id().unwrap_or_default();
+
+    let transfer = async move {
+        send_fds(&stream, listeners)?;
+        stream.set_nonblocking(true)?;
+
+        let mut stream = tokio::net::UnixStream::from_std(stream)?;
+        match stream.read_u8().await? {
+            READY => Ok(()),
+            _ => Err(io::Error::other(
+                "the new process sent an invalid acknowledgement",
+            )),
+        }
+    };
+
+    let result = tokio::select! {
+        result = timeout(HANDOFF_TIMEOUT, transfer) => result.unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the new process did not take over in time",
+            ))
+        }),
+        status = child.wait() => {
+            Err(io::Error::other(format!("the new process exited early with {}", status?)))
+        }
+    };
+
+    if result.is_err() {
+        let _ = child.start_kill();
+    }
+
+    result.map(|_| pid)
+}
+
+/// Clears close-on-exec on `fd` so the new process inherits it.
+fn set_inheritable(fd: RawFd) -> std::io::Result<()> {
+    // SAFETY: fcntl only reads and changes the flags of the descriptor.
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+
+    // SAFETY: see above.
+    if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFD, flags & !libc::FD_CLOEXEC) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Takes over the listeners from the process that started this one, if it was started for an upgrade.
+///
+/// The returned stream is used to acknowledge the upgrade with [`acknowledge`] once the server is accepting connections.
+pub(crate) fn receive_listeners() -> io::Result<Option<(Vec<std::net::TcpListener>, UnixStream)>> {
+    let Some(value) = env::var_os(UPGRADE_FD_ENV) else {
+        return Ok(None);
+    };
+
+    let Some(fd) = inherited_fd(
+        &value.to_string_lossy(),
+        std::os::unix::process::parent_id(),
+    )?
+    else {
+        return Ok(None);
+    };
+
+    // the environment is only read, changing it while the runtime's threads may read it is not safe
+    if RECEIVED.swap(true, Ordering::SeqCst) {
+        return Ok(None);
+    }
+
+    // SAFETY: the descriptor was inherited from the old process and stays open while it is borrowed.
+    let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+    let socket = SockRef::from(&borrowed);
+
+    if socket.domain()? != Domain::UNIX || socket.r#type()? != Type::STREAM {
+        return Err(io::Error::other(format!(
+            "{} does not name a unix stream socket",
+            UPGRADE_FD_ENV
+        )));
+    }
+
+    // SAFETY: the descriptor is only taken once, see `RECEIVED`.
+    let stream = unsafe { UnixStream::from_raw_fd(fd) };
+    SockRef::from(&stream).set_cloexec(true)?;
+
+    let listeners = recv_fds(&stream)?
+        .into_iter()
+        .map(std::net::TcpListener::from)
+        .collect();
+
+    Ok(Some((listeners, stream)))
+}
+
+/// Parses the value of [`UPGRADE_FD_ENV`], the descriptor is only meant for a process started by `parent`.
+pub(crate) fn inherited_fd(value: &str, parent: u32) -> io::Result<Option<RawFd>> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not `<pid>:<fd>`", UPGRADE_FD_ENV),
+        )
+    };
+
+    let (pid, fd) = value.split_once(':').ok_or_else(invalid)?;
+    let pid: u32 = pid.parse().map_err(|_| invalid())?;
+
+    if pid != parent {
+        return Ok(None);
+    }
+
+    fd.parse().map(Some).map_err(|_| invalid())
+}
+
+/// Tells the old process that this process is now accepting connections.
+pub(crate) fn acknowledge(stream: UnixStream) -> io::Result<()> {
+    use std::io::Write;
+
+    (&stream).write_all(&[READY])
+}
+
+/// Sends `fds` over `stream` as `SCM_RIGHTS` ancillary data.
+pub(crate) fn send_fds(stream: &UnixStream, fds: &[OwnedFd]) -> io::Result<()> {
+    if fds.len() > MAX_LISTENERS {
+        return Err(io::Error::other(format!(
+            "at most {} listeners can be handed over",
+            MAX_LISTENERS
+        )));
+    }
+
+    let raw_fds = fds.iter().map(AsRawFd::as_raw_fd).collect::<Vec<_>>();
+    let mut count = (fds.len() as u32).to_be_bytes();
+    let mut control = ControlBuffer::new(fds.len());
+
+    let mut iov = libc::iovec {
+        iov_base: count.as_mut_ptr().cast(),
+        iov_len: count.len(),
+    };
+
+    // SAFETY: an all zero msghdr is a valid empty message.
+    let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+    message.msg_iov = &mut iov;
+    message.msg_iovlen = 1;
+
+    if !raw_fds.is_empty() {
+        message.msg_control = control.as_mut_ptr();
+        message.msg_controllen = control.len() as _;
+
+        // SAFETY: the control buffer is aligned and has room for a header with `raw_fds.len()` descriptors.
+        unsafe {
+            let header = libc::CMSG_FIRSTHDR(&message);
+            (*header).cmsg_level = libc::SOL_SOCKET;
+            (*header).cmsg_type = libc::SCM_RIGHTS;
+            (*header).cmsg_len =
+                libc::CMSG_LEN(std::mem::size_of_val(raw_fds.as_slice()) as u32) as _;
+            std::ptr::copy_nonoverlapping(
+                raw_fds.as_ptr(),
+                libc::CMSG_DATA(header).cast::<RawFd>(),
+                raw_fds.len(),
+            );
+        }
+    }
+
+    // SAFETY: the message only points to buffers that outlive the call.
+    if unsafe { libc::sendmsg(stream.as_raw_fd(), &message, 0) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Receives the file descriptors sent with [`send_fds`].
+pub(crate) fn recv_fds(stream: &UnixStream) -> io::Result<Vec<OwnedFd>> {
+    let mut count = [0; 4];
+    let mut control = ControlBuffer::new(MAX_LISTENERS);
+
+    let mut iov = libc::iovec {
+        iov_base: count.as_mut_ptr().cast(),
+        iov_len: count.len(),
+    };
+
+    // SAFETY: an all zero msghdr is a valid empty message.
+    let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+    message.msg_iov = &mut iov;
+    message.msg_iovlen = 1;
+    message.msg_control = control.as_mut_ptr();
+    message.msg_controllen = control.len() as _;
+
+    // SAFETY: the message only points to buffers that outlive the call.
+    let received = unsafe { libc::recvmsg(stream.as_raw_fd(), &mut message, 0) };
+
+    if received < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut fds = Vec::new();
+
+    // SAFETY: the headers are walked with the CMSG macros within the length the kernel filled in.
+    unsafe {
+        let mut header = libc::CMSG_FIRSTHDR(&message);
+
+        while !header.is_null() {
+            if (*header).cmsg_level == libc::SOL_SOCKET && (*header).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(header);
+                let len = (*header).cmsg_len as usize - (data as usize - header as usize);
+
+                for index in 0..len / std::mem::size_of::<RawFd>() {
+                    let fd = std::ptr::read_unaligned(data.cast::<RawFd>().add(index));
+                    // the file descriptor was just received and is not owned by anything else
+                    fds.push(OwnedFd::from_raw_fd(fd));
+                }
+            }
+
+            header = libc::CMSG_NXTHDR(&message, header);
+        }
+    }
+
+    for fd in &fds {
+        SockRef::from(fd).set_cloexec(true)?;
+    }
+
+    if message.msg_flags & libc::MSG_CTRUNC != 0
+        || received as usize != count.len()
+        || fds.len() != u32::from_be_bytes(count) as usize
+    {
+        return Err(io::Error::other(
+            "did not receive every listener from the old process",
+        ));
+    }
+
+    Ok(fds)
+}
+
+/// A buffer for the ancillary data of `fds` file descriptors, aligned for `cmsghdr`.
+struct ControlBuffer {
+    buffer: Vec<u64>,
+    len: usize,
+}
+
+impl ControlBuffer {
+    fn new(fds: usize) -> Self {
+        // SAFETY: CMSG_SPACE only does arithmetic.
+        let len = unsafe { libc::CMSG_SPACE((fds * std::mem::size_of::<RawFd>()) as u32) } as usize;
+
+        Self {
+            buffer: vec![0; len.div_ceil(8)],
+            len,
+        }
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut libc::c_void {
+        self.buffer.as_mut_ptr().cast()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
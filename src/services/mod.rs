@@ -3,18 +3,78 @@
 use crate::traits::handler::Handler;
 use http::Method;
 
+pub use crate::route::HandlerFn;
+
 /// A trait for defining a service endpoint.
+///
+/// It is usually implemented with one of the service macros like [`get`](crate::prelude::get),
+/// which also accept a name for the route and layers that only apply to that endpoint.
+/// There is a macro for every method, `get`, `post`, `put`, `delete`, `patch`, `head`, `options`,
+/// `trace` and `connect`.
+///
+/// # Examples
+/// ```
+/// # #![feature(impl_trait_in_assoc_type)]
+/// # use tosic_http::prelude::{get, HttpResponse, HttpServer};
+/// # use tower::util::MapResponseLayer;
+/// #[get("/", name = "index", layer = MapResponseLayer::new(|response: HttpResponse| response))]
+/// async fn index() -> &'static str {
+///     "Hello"
+/// }
+///
+/// let builder = HttpServer::builder()
+///     .service(index)
+///     .bind("127.0.0.1:8080");
+/// ```
+///
+/// [`route`](crate::prelude::route) registers one handler for several methods:
+/// ```
+/// # #![feature(impl_trait_in_assoc_type)]
+/// # use tosic_http::prelude::{route, HttpRequest, HttpServer};
+/// #[route("/items", method = "GET", method = "POST")]
+/// async fn items(req: HttpRequest) -> String {
+///     req.method().to_string()
+/// }
+///
+/// let builder = HttpServer::builder()
+///     .service(items)
+///     .bind("127.0.0.1:8080");
+/// ```
+///
+/// The macros check the route pattern at compile time, so a typo in a route fails the build instead of
+/// showing up when the server starts:
+/// ```compile_fail
+/// # #![feature(impl_trait_in_assoc_type)]
+/// # use tosic_http::prelude::get;
+/// #[get("/users/{id}/posts/{id}")] // error: duplicate parameter `id`
+/// async fn post() -> &'static str {
+///     "Hello"
+/// }
+/// ```
+///
+/// They also warn if a [`Path`](crate::extractors::path::Path) argument holds a tuple, an array or a
+/// primitive that can't have as many parameters as the route.
 pub trait HttpService<Args>: Handler<Args> {
-    /// The HTTP method for the service endpoint.
-    const METHOD: Method = Method::GET;
+    /// The HTTP methods the service endpoint handles.
+    const METHODS: &'static [Method] = &[Method::GET];
     /// The path of the service endpoint.
     const PATH: &'static str = "";
-    /// Gets the HTTP method for the service
-    fn method(&self) -> Method {
-        Self::METHOD
+    /// The name of the route, used to build its URL with [`HttpRequest::url_for`](crate::request::HttpRequest::url_for).
+    const NAME: Option<&'static str> = None;
+    /// Gets the HTTP methods for the service
+    fn methods(&self) -> &'static [Method] {
+        Self::METHODS
     }
     /// Gets the path for the service
     fn path(&self) -> &'static str {
         Self::PATH
     }
+    /// Gets the name of the route
+    fn name(&self) -> Option<&'static str> {
+        Self::NAME
+    }
+    /// Wraps the handler in the layers of the service, by default there are none
+    fn layer(handler: HandlerFn) -> HandlerFn {
+        handler
+    }
 }
@@ -0,0 +1,58 @@
+//! State that is created asynchronously while the server is being built
+
+use crate::error::ServerError;
+use crate::server::lifecycle::BoxError;
+use crate::state::State;
+use futures::future::BoxFuture;
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Inserts the value created by a factory into the state.
+type Insert = Box<dyn FnOnce(&mut State) + Send>;
+
+/// A factory that creates a single state value.
+pub(crate) type StateFactory =
+    dyn Fn() -> BoxFuture<'static, Result<Insert, BoxError>> + Send + Sync;
+
+#[derive(Clone, Default)]
+/// The registered state factories, run in the order they were added.
+pub(crate) struct StateFactories(Vec<Arc<StateFactory>>);
+
+impl Debug for StateFactories {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("StateFactories")
+            .field(&self.0.len())
+            .finish()
+    }
+}
+
+impl StateFactories {
+    /// Adds a factory for a value of type `S`.
+    pub(crate) fn push<S, F, Fut, E>(&mut self, factory: F)
+    where
+        S: Send + Sync + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<S, E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        self.0.push(Arc::new(move || {
+            let future = factory();
+
+            Box::pin(async move {
+                let value = future.await.map_err(Into::into)?;
+                Ok(Box::new(move |state: &mut State| state.insert(value)) as Insert)
+            })
+        }));
+    }
+
+    /// Runs every factory and inserts the values into `state`, stopping at the first one that fails.
+    pub(crate) async fn build(&self, state: &mut State) -> Result<(), ServerError> {
+        for factory in &self.0 {
+            let insert = factory().await.map_err(ServerError::StateFactory)?;
+            insert(state);
+        }
+
+        Ok(())
+    }
+}
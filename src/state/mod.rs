@@ -0,0 +1,204 @@
+//! State management
+
+use crate::traits::from_ref::FromRef;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+pub(crate) mod factory;
+pub mod reloadable;
+
+pub use reloadable::Reloadable;
+
+/// A type-erased `Arc` holding a state value.
+type AnyArc = Arc<dyn Any + Send + Sync>;
+
+/// The map backing [`State`], keyed by the [`TypeId`] of each value.
+type StateMap = HashMap<TypeId, Entry, BuildHasherDefault<TypeIdHasher>>;
+
+/// Derives the entry of a value from another value in the state, see [`FromRef`].
+type Derive = Arc<dyn Fn(&State) -> Option<Entry> + Send + Sync>;
+
+/// Turns the value of an [`Entry`] back into an `Arc<T>`.
+type Load<T> = fn(&AnyArc) -> Option<Arc<T>>;
+
+#[derive(Clone)]
+/// A value in the state.
+///
+/// Sized values are stored directly, unsized values such as trait objects can't be turned into
+/// `dyn Any` and are stored as an `Arc<T>` instead. `load` is the [`Load<T>`] for the way the value was stored.
+struct Entry {
+    value: AnyArc,
+    load: &'static (dyn Any + Send + Sync),
+}
+
+impl Entry {
+    /// Stores a sized value directly
+    fn value<T: Send + Sync + 'static>(value: Arc<T>) -> Self {
+        Entry {
+            value,
+            load: Loader::<T>::VALUE,
+        }
+    }
+
+    /// Stores a value that may be unsized behind another `Arc`
+    fn shared<T: ?Sized + Send + Sync + 'static>(value: Arc<T>) -> Self {
+        Entry {
+            value: Arc::new(value),
+            load: Loader::<T>::SHARED,
+        }
+    }
+
+    /// Returns the value if it is a `T`
+    fn get<T: ?Sized + Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.load
+            .downcast_ref::<Load<T>>()
+            .and_then(|load| load(&self.value))
+    }
+}
+
+/// Holds the [`Load`] functions for `T`.
+struct Loader<T: ?Sized>(PhantomData<T>);
+
+impl<T: Send + Sync + 'static> Loader<T> {
+    const VALUE: &'static Load<T> =
+        &((|value: &AnyArc| value.clone().downcast::<T>().ok()) as Load<T>);
+}
+
+impl<T: ?Sized + Send + Sync + 'static> Loader<T> {
+    const SHARED: &'static Load<T> =
+        &((|value: &AnyArc| value.downcast_ref::<Arc<T>>().cloned()) as Load<T>);
+}
+
+#[derive(Clone, Default)]
+/// State management
+///
+/// The state is filled while the server is being built and frozen once
+/// [`HttpServerBuilder::build`](crate::server::builder::HttpServerBuilder::build) finishes,
+/// reading from it never takes a lock. Values that need to change while the server is running
+/// should be added with [`HttpServerBuilder::app_state_mut`](crate::server::builder::HttpServerBuilder::app_state_mut).
+pub struct State {
+    data: Arc<StateMap>,
+    derived: Arc<HashMap<TypeId, Derive, BuildHasherDefault<TypeIdHasher>>>,
+    parent: Option<Arc<State>>,
+}
+
+impl Debug for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("State")
+            .field("len", &self.data.len())
+            .field("derived", &self.derived.len())
+            .field("parent", &self.parent)
+            .finish()
+    }
+}
+
+impl State {
+    /// Creates a new empty state
+    pub fn new() -> Self {
+        State::default()
+    }
+
+    /// Inserts a value into the state
+    ///
+    /// This only changes this instance, clones that were already handed out keep the values they had.
+    pub(crate) fn insert<T: Send + Sync + 'static>(&mut self, value: T) {
+        Arc::make_mut(&mut self.data).insert(TypeId::of::<T>(), Entry::value(Arc::new(value)));
+    }
+
+    /// Inserts a shared value into the state, `T` can be unsized like `dyn Trait`
+    pub(crate) fn insert_arc<T: ?Sized + Send + Sync + 'static>(&mut self, value: Arc<T>) {
+        Arc::make_mut(&mut self.data).insert(TypeId::of::<T>(), Entry::shared(value));
+    }
+
+    /// Makes `T` available by deriving it from the `P` in the state.
+    ///
+    /// Until the state is [frozen](State::freeze) the value is derived whenever it is requested.
+    pub(crate) fn insert_derived<P, T>(&mut self)
+    where
+        P: Send + Sync + 'static,
+        T: FromRef<P> + Send + Sync + 'static,
+    {
+        let derive: Derive = Arc::new(|state: &State| {
+            state
+                .get::<P>()
+                .map(|parent| Entry::value(Arc::new(T::from_ref(&parent))))
+        });
+
+        Arc::make_mut(&mut self.derived).insert(TypeId::of::<T>(), derive);
+    }
+
+    /// Derives every value added with [`State::insert_derived`] once and stores it like any other value.
+    ///
+    /// Called when nothing can be inserted into the state anymore, so the derived values can't go stale.
+    pub(crate) fn freeze(&mut self) {
+        let derived = Arc::clone(&self.derived);
+
+        for (key, derive) in derived.iter() {
+            if self.data.contains_key(key) {
+                continue;
+            }
+
+            if let Some(entry) = derive(self) {
+                Arc::make_mut(&mut self.data).insert(*key, entry);
+            }
+        }
+
+        self.derived = Arc::default();
+    }
+
+    /// Returns whether nothing was inserted into this state, ignoring any parent
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty() && self.derived.is_empty()
+    }
+
+    /// Returns this state with `parent` as the fallback for values it does not contain itself
+    pub(crate) fn with_parent(&self, parent: State) -> State {
+        State {
+            data: Arc::clone(&self.data),
+            derived: Arc::clone(&self.derived),
+            parent: Some(Arc::new(parent)),
+        }
+    }
+
+    /// Gets a value from the state
+    ///
+    /// Values inserted directly are preferred over values derived with [`FromRef`],
+    /// values that are in neither are looked up in the parent state if there is one.
+    pub fn get<T: ?Sized + Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        let key = TypeId::of::<T>();
+
+        if let Some(entry) = self.data.get(&key) {
+            return entry.get::<T>();
+        }
+
+        if let Some(derive) = self.derived.get(&key) {
+            return derive(self).and_then(|entry| entry.get::<T>());
+        }
+
+        self.parent.as_ref().and_then(|parent| parent.get::<T>())
+    }
+}
+
+#[derive(Default)]
+/// A hasher for [`TypeId`] keys, they are already unique hashes so there is no need to hash them again.
+struct TypeIdHasher(u64);
+
+impl Hasher for TypeIdHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ u64::from(*byte);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = value;
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
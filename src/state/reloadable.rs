@@ -0,0 +1,81 @@
+//! State values that can be replaced while the server is running
+
+use std::fmt::Debug;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// A state value that can be swapped atomically while the server is running.
+///
+/// `Reloadable` is a handle, cloning it gives another handle to the same value.
+/// Register one clone with [`HttpServerBuilder::app_state_reloadable`](crate::server::builder::HttpServerBuilder::app_state_reloadable)
+/// and keep another outside the server to [`store`](Reloadable::store) new values.
+/// Handlers read the value with the [`Snapshot`](crate::extractors::Snapshot) extractor,
+/// which keeps the value that was current when the request started even if it is replaced during the request.
+///
+/// # Examples
+/// ```
+/// # use tosic_http::prelude::{HttpServer, Snapshot};
+/// # use tosic_http::state::Reloadable;
+/// struct RateLimit {
+///     requests: u32,
+/// }
+///
+/// async fn limit(limit: Snapshot<RateLimit>) -> String {
+///     limit.requests.to_string()
+/// }
+///
+/// let rate_limit = Reloadable::new(RateLimit { requests: 100 });
+///
+/// let builder = HttpServer::builder()
+///     .app_state_reloadable(rate_limit.clone())
+///     .bind("127.0.0.1:8080");
+///
+/// rate_limit.store(RateLimit { requests: 200 });
+/// ```
+pub struct Reloadable<T> {
+    sender: Arc<watch::Sender<Arc<T>>>,
+}
+
+impl<T> Reloadable<T> {
+    /// Creates a new handle holding `value`
+    pub fn new(value: T) -> Self {
+        Self {
+            sender: Arc::new(watch::Sender::new(Arc::new(value))),
+        }
+    }
+
+    /// Returns the current value
+    pub fn load(&self) -> Arc<T> {
+        self.sender.borrow().clone()
+    }
+
+    /// Replaces the current value and notifies every subscriber
+    pub fn store(&self, value: T) {
+        self.sender.send_replace(Arc::new(value));
+    }
+
+    /// Builds a new value from the current one and stores it
+    pub fn update(&self, update: impl FnOnce(&T) -> T) {
+        self.sender
+            .send_modify(|current| *current = Arc::new(update(current)));
+    }
+
+    /// Subscribes to changes, [`watch::Receiver::changed`] resolves every time a new value is stored
+    pub fn subscribe(&self) -> watch::Receiver<Arc<T>> {
+        self.sender.subscribe()
+    }
+}
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: Arc::clone(&self.sender),
+        }
+    }
+}
+
+impl<T: Debug> Debug for Reloadable<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Reloadable").field(&self.load()).finish()
+    }
+}
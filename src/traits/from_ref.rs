@@ -0,0 +1,49 @@
+//! # FromRef
+//!
+//! The `FromRef` trait is used to derive a part of the app state from a larger state type
+
+/// Derives `Self` from a reference to `T`
+///
+/// Register a derivation with [`HttpServerBuilder::app_state_from_ref`](crate::server::builder::HttpServerBuilder::app_state_from_ref)
+/// and `Data<Self>` can be extracted whenever a `T` was added as app state.
+/// The value is derived once when the server is built and shared by every extraction after that.
+/// Changes to the parent are not reflected, so derive from state added with
+/// [`app_state`](crate::server::builder::HttpServerBuilder::app_state) rather than from state that can change
+/// at runtime like [`app_state_mut`](crate::server::builder::HttpServerBuilder::app_state_mut) or a
+/// [`Reloadable`](crate::state::Reloadable).
+///
+/// # Examples
+/// ```
+/// # use tosic_http::prelude::{Data, FromRef, HttpServer};
+/// struct Config {
+///     database_url: String,
+///     port: u16,
+/// }
+///
+/// struct DatabaseUrl(String);
+///
+/// impl FromRef<Config> for DatabaseUrl {
+///     fn from_ref(config: &Config) -> Self {
+///         DatabaseUrl(config.database_url.clone())
+///     }
+/// }
+///
+/// async fn handler(url: Data<DatabaseUrl>) -> String {
+///     url.0.0.clone()
+/// }
+///
+/// let builder = HttpServer::builder()
+///     .app_state(Config { database_url: "postgres://localhost".to_string(), port: 8080 })
+///     .app_state_from_ref::<Config, DatabaseUrl>()
+///     .bind("127.0.0.1:8080");
+/// ```
+pub trait FromRef<T> {
+    /// Derives the value from `input`
+    fn from_ref(input: &T) -> Self;
+}
+
+impl<T: Clone> FromRef<T> for T {
+    fn from_ref(input: &T) -> Self {
+        input.clone()
+    }
+}
@@ -1,9 +1,13 @@
 //! Main traits of the framework
 
+pub mod from_ref;
 pub mod from_request;
 pub mod handler;
 pub mod responder;
+pub mod typed_path;
 
+pub use from_ref::FromRef;
 pub use from_request::FromRequest;
 pub use handler::Handler;
 pub use responder::Responder;
+pub use typed_path::TypedPath;
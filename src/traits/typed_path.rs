@@ -0,0 +1,80 @@
+//! # TypedPath
+//!
+//! The `TypedPath` trait ties a struct to a route pattern, it is usually derived with
+//! [`TypedPath`](tosic_http_macro::TypedPath)
+
+use crate::error::UrlGenerationError;
+use crate::extractors::path::PathDeserializer;
+use crate::extractors::ExtractionError;
+use crate::request::Params;
+use crate::route::Route;
+use serde::de::DeserializeOwned;
+
+/// A struct with a field for every parameter of a route.
+///
+/// It is extracted from the parameters of the matched route like [`Path`](crate::extractors::Path)
+/// and its URL is built from the values of the fields, so the route pattern is only written once.
+/// Register handlers for it with [`typed`](crate::resource::typed) or by passing the type to a service
+/// macro like `#[get(UserPath)]`.
+///
+/// # Examples
+/// ```
+/// # #![feature(impl_trait_in_assoc_type)]
+/// # use tosic_http::prelude::{get, HttpServer, TypedPath};
+/// # use serde::Deserialize;
+/// #[derive(TypedPath, Deserialize)]
+/// #[typed_path("/users/{id}/posts/{post}")]
+/// struct PostPath {
+///     id: u32,
+///     post: String,
+/// }
+///
+/// #[get(PostPath)]
+/// async fn post(path: PostPath) -> String {
+///     format!("{} by {}", path.post, path.id)
+/// }
+///
+/// let path = PostPath { id: 7, post: "hello world".to_string() };
+/// assert_eq!(path.to_url().unwrap(), "/users/7/posts/hello%20world");
+/// assert_eq!(path.to_string(), "/users/7/posts/hello%20world");
+///
+/// let builder = HttpServer::builder()
+///     .service(post)
+///     .bind("127.0.0.1:8080");
+/// ```
+pub trait TypedPath: DeserializeOwned {
+    /// The route pattern
+    const PATH: &'static str;
+
+    /// The value of every parameter of the route by name
+    fn params(&self) -> Vec<(&'static str, String)>;
+
+    /// Deserializes the path from the parameters of a matched route
+    fn from_params(params: &Params) -> Result<Self, ExtractionError> {
+        Self::deserialize(PathDeserializer::new(params))
+            .map_err(|error| ExtractionError::Path(error.to_string()))
+    }
+
+    /// Builds the percent-encoded URL of the route from the values of the fields
+    fn to_url(&self) -> Result<String, UrlGenerationError> {
+        let params = self
+            .params()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+
+        Route::parse(Self::PATH)?.url_for(&params)
+    }
+
+    /// Builds the percent-encoded URL like [`TypedPath::to_url`] without checking the values of the fields
+    /// against the constraints of the route, this is what the derived `Display` writes
+    fn to_url_unchecked(&self) -> Result<String, UrlGenerationError> {
+        let params = self
+            .params()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+
+        Route::parse(Self::PATH)?.url_for_unchecked(&params)
+    }
+}
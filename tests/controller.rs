@@ -0,0 +1,120 @@
+#![feature(impl_trait_in_assoc_type)]
+#![feature(macro_attr)]
+
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tosic_http::prelude::{controller, get, HttpServer, Path};
+use tosic_http::scope::Scope;
+
+/// An attribute of another crate that happens to share its name with a service macro
+mod other {
+    macro_rules! route {
+        attr() ($($item:tt)*) => { $($item)* };
+    }
+
+    pub(crate) use route;
+}
+
+struct Users;
+
+#[controller("/api/users/")]
+impl Users {
+    #[get("/{id}")]
+    async fn show(id: Path<u32>) -> String {
+        format!("user {}", id.into_inner())
+    }
+
+    #[tosic_http::prelude::post("/")]
+    async fn create() -> &'static str {
+        "created"
+    }
+
+    #[other::route]
+    fn count() -> usize {
+        2
+    }
+}
+
+#[controller("/")]
+mod root {
+    use tosic_http::prelude::get;
+
+    #[get("/")]
+    async fn index() -> &'static str {
+        "index"
+    }
+
+    #[get("health")]
+    async fn health() -> &'static str {
+        "ok"
+    }
+
+    #[super::other::route]
+    pub fn version() -> &'static str {
+        "1"
+    }
+}
+
+/// Serves `scope` on a free port and returns its address
+async fn serve(scope: Scope) -> SocketAddr {
+    let server = HttpServer::builder()
+        .scope(scope)
+        .bind("127.0.0.1:0")
+        .build()
+        .await
+        .unwrap();
+
+    let addr = server.local_addrs().unwrap()[0];
+    tokio::spawn(server.serve());
+    addr
+}
+
+/// Sends a request with `method` for `path` to `addr` and returns the raw response
+async fn send(addr: SocketAddr, method: &str, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = format!(
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\n\r\n",
+        method, path
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn impl_controller_dispatches_to_its_handlers() {
+    let addr = serve(Users::scope()).await;
+
+    let response = send(addr, "GET", "/api/users/7").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.ends_with("user 7"));
+
+    let response = send(addr, "POST", "/api/users").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.ends_with("created"));
+
+    let response = send(addr, "GET", "/users/7").await;
+    assert!(response.starts_with("HTTP/1.1 404"));
+}
+
+#[tokio::test]
+async fn module_controller_dispatches_to_its_handlers() {
+    let addr = serve(root::scope()).await;
+
+    let response = send(addr, "GET", "/").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.ends_with("index"));
+
+    let response = send(addr, "GET", "/health").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.ends_with("ok"));
+}
+
+#[test]
+fn foreign_attributes_are_left_alone() {
+    assert_eq!(Users::count(), 2);
+    assert_eq!(root::version(), "1");
+}
@@ -0,0 +1,57 @@
+use serde::Deserialize;
+use tosic_http::error::UrlGenerationError;
+use tosic_http::prelude::TypedPath;
+use tosic_http::request::Params;
+
+#[derive(TypedPath, Deserialize, Debug, PartialEq)]
+#[typed_path("/users/{user-id:int}/posts/{post}")]
+struct PostPath {
+    #[serde(rename = "user-id")]
+    user_id: String,
+    #[serde(rename(serialize = "ignored"))]
+    post: String,
+}
+
+#[test]
+fn renamed_fields_use_the_parameter_name() {
+    let path = PostPath {
+        user_id: "7".to_string(),
+        post: "hello world".to_string(),
+    };
+
+    assert_eq!(
+        path.params(),
+        vec![
+            ("user-id", "7".to_string()),
+            ("post", "hello world".to_string())
+        ]
+    );
+    assert_eq!(path.to_url().unwrap(), "/users/7/posts/hello%20world");
+    assert_eq!(path.to_string(), "/users/7/posts/hello%20world");
+
+    let mut params = Params::new();
+    params.insert("user-id".to_string(), "7".to_string());
+    params.insert("post".to_string(), "hello world".to_string());
+
+    assert_eq!(PostPath::from_params(&params).unwrap(), path);
+}
+
+#[test]
+fn invalid_values_are_an_error() {
+    let path = PostPath {
+        user_id: "me".to_string(),
+        post: "hello".to_string(),
+    };
+
+    assert_eq!(
+        path.to_url(),
+        Err(UrlGenerationError::InvalidParameter {
+            name: "user-id".to_string(),
+            value: "me".to_string(),
+            constraint: "int".to_string(),
+        })
+    );
+
+    // `Display` writes the URL without checking the constraints, so it never fails
+    assert_eq!(path.to_string(), "/users/me/posts/hello");
+}
@@ -0,0 +1,14 @@
+#![feature(impl_trait_in_assoc_type)]
+use tosic_http::prelude::controller;
+
+struct Users<T>(T);
+
+#[controller("/users")]
+impl<T> Users<T> {
+    #[get("/")]
+    async fn index() -> &'static str {
+        "index"
+    }
+}
+
+fn main() {}
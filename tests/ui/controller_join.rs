@@ -0,0 +1,24 @@
+#![feature(impl_trait_in_assoc_type)]
+use tosic_http::prelude::controller;
+
+struct Files;
+
+#[controller("/files/**")]
+impl Files {
+    #[get("/raw")]
+    async fn raw() -> &'static str {
+        "raw"
+    }
+}
+
+struct Users;
+
+#[controller("/users/{id}")]
+impl Users {
+    #[get("/posts/{id}")]
+    async fn post() -> &'static str {
+        "post"
+    }
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+#![feature(impl_trait_in_assoc_type)]
+#![feature(proc_macro_hygiene)]
+use tosic_http::prelude::controller;
+
+#[controller("/users")]
+mod users;
+
+fn main() {}
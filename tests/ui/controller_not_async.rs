@@ -0,0 +1,14 @@
+#![feature(impl_trait_in_assoc_type)]
+use tosic_http::prelude::controller;
+
+struct Users;
+
+#[controller("/users")]
+impl Users {
+    #[get("/")]
+    fn index() -> &'static str {
+        "index"
+    }
+}
+
+fn main() {}
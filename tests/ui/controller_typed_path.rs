@@ -0,0 +1,21 @@
+#![feature(impl_trait_in_assoc_type)]
+use serde::Deserialize;
+use tosic_http::prelude::{controller, TypedPath};
+
+#[derive(TypedPath, Deserialize)]
+#[typed_path("/users/{id}")]
+struct UserPath {
+    id: u32,
+}
+
+struct Users;
+
+#[controller("/api")]
+impl Users {
+    #[get(UserPath)]
+    async fn show() -> &'static str {
+        "user"
+    }
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#![feature(impl_trait_in_assoc_type)]
+use tosic_http::prelude::get;
+
+#[get("/users/{id}/posts/{id}")]
+async fn post() -> &'static str {
+    "post"
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#![feature(impl_trait_in_assoc_type)]
+use tosic_http::prelude::get;
+
+#[get("/users/{id:[0-9}")]
+async fn user() -> &'static str {
+    "user"
+}
+
+fn main() {}
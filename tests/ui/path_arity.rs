@@ -0,0 +1,10 @@
+#![feature(impl_trait_in_assoc_type)]
+#![deny(deprecated)]
+use tosic_http::prelude::{get, Path};
+
+#[get("/users/{id}/posts/{post}")]
+async fn post(path: Path<u32>) -> String {
+    path.into_inner().to_string()
+}
+
+fn main() {}
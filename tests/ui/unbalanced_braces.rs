@@ -0,0 +1,14 @@
+#![feature(impl_trait_in_assoc_type)]
+use tosic_http::prelude::get;
+
+#[get("/users/{id")]
+async fn unclosed() -> &'static str {
+    "unclosed"
+}
+
+#[get("/users/id}")]
+async fn unmatched() -> &'static str {
+    "unmatched"
+}
+
+fn main() {}
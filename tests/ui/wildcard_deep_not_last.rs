@@ -0,0 +1,9 @@
+#![feature(impl_trait_in_assoc_type)]
+use tosic_http::prelude::get;
+
+#[get("/files/**/raw")]
+async fn file() -> &'static str {
+    "file"
+}
+
+fn main() {}
@@ -0,0 +1,229 @@
+use crate::route;
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    parse_macro_input, Attribute, FnArg, ImplItem, Item, ItemImpl, ItemMod, LitStr, Meta, Type,
+};
+
+/// The attributes that turn a function into a service
+const SERVICE_ATTRIBUTES: &[&str] = &[
+    "get", "post", "put", "delete", "patch", "head", "options", "trace", "connect", "route",
+];
+
+/// The item a controller is attached to
+enum Controller {
+    Module(ItemMod),
+    Impl(ItemImpl),
+}
+
+impl Parse for Controller {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        match input.parse()? {
+            Item::Mod(item) => Ok(Controller::Module(item)),
+            Item::Impl(item) => Ok(Controller::Impl(item)),
+            item => Err(syn::Error::new_spanned(
+                item,
+                "`controller` can only be used on a module or an impl block",
+            )),
+        }
+    }
+}
+
+pub(crate) fn controller(args: TokenStream, input: TokenStream) -> TokenStream {
+    let prefix = parse_macro_input!(args as LitStr);
+    let controller = parse_macro_input!(input as Controller);
+
+    let expanded = route::parse(&prefix.value())
+        .map_err(|error| error.into_syn(&prefix))
+        .and_then(|_| match controller {
+            Controller::Module(item) => module(&prefix, item),
+            Controller::Impl(item) => impl_block(&prefix, item),
+        });
+
+    match expanded {
+        Ok(expanded) => expanded.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Prefixes the services in an inline module and adds `scope()` to it
+fn module(prefix: &LitStr, mut item: ItemMod) -> syn::Result<TokenStream2> {
+    let crate_name = Ident::new("tosic_http", Span::call_site());
+
+    let Some((_, items)) = &mut item.content else {
+        return Err(syn::Error::new_spanned(
+            &item,
+            "the module has to be inline, `controller` can't see into other files",
+        ));
+    };
+
+    let mut services = Vec::new();
+
+    for item in items.iter_mut() {
+        let Item::Fn(function) = item else {
+            continue;
+        };
+        let Some(index) = service_attribute(&function.attrs) else {
+            continue;
+        };
+
+        prefix_attribute(prefix, &mut function.attrs[index])?;
+        services.push(function.sig.ident.clone());
+    }
+
+    items.push(syn::parse_quote! {
+        /// Creates a scope with every route of the controller
+        pub fn scope() -> #crate_name::scope::Scope {
+            #crate_name::scope::Scope::new("/") #(.service(#services))*
+        }
+    });
+
+    Ok(quote!(#item))
+}
+
+/// Moves the services of an impl block into a hidden module and adds `scope()` to the type.
+///
+/// The service macros turn a function into a struct, which can't be declared in an impl block, so every
+/// service gets a function in the module that calls the associated function.
+fn impl_block(prefix: &LitStr, mut item: ItemImpl) -> syn::Result<TokenStream2> {
+    let crate_name = Ident::new("tosic_http", Span::call_site());
+
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "a controller can't be generic",
+        ));
+    }
+
+    let self_ty = item.self_ty.clone();
+    let type_name = match &*self_ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last().map(|s| &s.ident),
+        _ => None,
+    }
+    .ok_or_else(|| syn::Error::new_spanned(&self_ty, "expected the name of a type"))?;
+    let routes = format_ident!("__{}_routes", type_name);
+
+    let mut services = Vec::new();
+    let mut handlers = Vec::new();
+
+    for impl_item in item.items.iter_mut() {
+        let ImplItem::Fn(function) = impl_item else {
+            continue;
+        };
+        let Some(index) = service_attribute(&function.attrs) else {
+            continue;
+        };
+
+        let mut attr = function.attrs.remove(index);
+        prefix_attribute(prefix, &mut attr)?;
+
+        if function.sig.asyncness.is_none() {
+            return Err(syn::Error::new_spanned(
+                function.sig.fn_token,
+                "async fn is required",
+            ));
+        }
+
+        let mut args = Vec::new();
+        let mut types = Vec::new();
+
+        for (index, input) in function.sig.inputs.iter().enumerate() {
+            match input {
+                FnArg::Receiver(receiver) => {
+                    return Err(syn::Error::new_spanned(
+                        receiver,
+                        "a handler in a controller can't take `self`",
+                    ))
+                }
+                FnArg::Typed(arg) => {
+                    args.push(format_ident!("arg{}", index));
+                    types.push(arg.ty.clone());
+                }
+            }
+        }
+
+        let ident = &function.sig.ident;
+        let output = &function.sig.output;
+        let doc_attrs = function
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"));
+
+        handlers.push(quote! {
+            #(#doc_attrs)*
+            #attr
+            pub(super) async fn #ident(#(#args: #types),*) #output {
+                <#self_ty>::#ident(#(#args),*).await
+            }
+        });
+        services.push(ident.clone());
+    }
+
+    Ok(quote! {
+        #item
+
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        mod #routes {
+            use super::*;
+
+            #(#handlers)*
+        }
+
+        impl #self_ty {
+            /// Creates a scope with every route of the controller
+            pub fn scope() -> #crate_name::scope::Scope {
+                #crate_name::scope::Scope::new("/") #(.service(#routes::#services))*
+            }
+        }
+    })
+}
+
+/// Returns the index of the service attribute like `#[get("/path")]` if the function has one.
+///
+/// Only a bare name or a path into `tosic_http` counts, so attributes of other crates that happen to be
+/// called `get` or `route` are left alone.
+fn service_attribute(attrs: &[Attribute]) -> Option<usize> {
+    attrs.iter().position(|attr| {
+        let segments = &attr.path().segments;
+        let is_service = segments
+            .last()
+            .is_some_and(|segment| SERVICE_ATTRIBUTES.iter().any(|name| segment.ident == name));
+        let is_ours = (segments.len() == 1 && attr.path().leading_colon.is_none())
+            || segments
+                .first()
+                .is_some_and(|segment| segment.ident == "tosic_http");
+
+        is_service && is_ours
+    })
+}
+
+/// Joins the pattern of a service attribute with the prefix of the controller
+fn prefix_attribute(prefix: &LitStr, attr: &mut Attribute) -> syn::Result<()> {
+    let Meta::List(list) = &mut attr.meta else {
+        return Err(syn::Error::new_spanned(
+            &attr,
+            "expected the route, like `#[get(\"/path\")]`",
+        ));
+    };
+
+    let (pattern, options) = list.parse_args_with(|input: ParseStream| {
+        if !input.peek(LitStr) {
+            return Err(input.error(
+                "expected the route as a string literal, a typed path is already the full path",
+            ));
+        }
+
+        let pattern: LitStr = input.parse()?;
+        let options: TokenStream2 = input.parse()?;
+
+        Ok((pattern, options))
+    })?;
+
+    let joined = LitStr::new(&route::join(prefix, &pattern)?, pattern.span());
+    list.tokens = quote!(#joined #options);
+
+    Ok(())
+}
@@ -1,4 +1,7 @@
+mod controller;
+mod route;
 mod service;
+mod typed_path;
 
 extern crate proc_macro;
 use proc_macro::TokenStream;
@@ -8,7 +11,7 @@ macro_rules! service_method {
         #[proc_macro_attribute]
         /// Generates a `HttpService` implemementation for the given HTTP method
         pub fn $ident(args: TokenStream, input: TokenStream) -> TokenStream {
-            $crate::service::service(args, input, $str)
+            $crate::service::service(args, input, Some($str))
         }
     };
 }
@@ -17,3 +20,38 @@ service_method!(get, "get");
 service_method!(post, "post");
 service_method!(put, "put");
 service_method!(delete, "delete");
+service_method!(patch, "patch");
+service_method!(head, "head");
+service_method!(options, "options");
+service_method!(trace, "trace");
+service_method!(connect, "connect");
+
+#[proc_macro_attribute]
+/// Generates a `HttpService` implementation that handles every method listed with `method = "..."`,
+/// like `#[route("/path", method = "GET", method = "POST")]`
+pub fn route(args: TokenStream, input: TokenStream) -> TokenStream {
+    service::service(args, input, None)
+}
+
+#[proc_macro_attribute]
+/// Groups the services in a module or `impl` block under the prefix in `#[controller("/prefix")]`.
+///
+/// The route of every function with a service attribute like `#[get("/{id}")]` is joined with the prefix,
+/// and the generated `scope()` function returns a `Scope` with all of them to register in one call.
+///
+/// The services of an `impl` block are declared in a module next to it, so the block has to be at the
+/// top level of a module rather than inside a function.
+pub fn controller(args: TokenStream, input: TokenStream) -> TokenStream {
+    controller::controller(args, input)
+}
+
+#[proc_macro_derive(TypedPath, attributes(typed_path))]
+/// Derives `TypedPath` for a struct with a field for every parameter of the route in `#[typed_path("/path/{param}")]`.
+///
+/// It also implements `FromRequest`, which needs the struct to implement `Deserialize`,
+/// and `Display`, which builds the URL of the route from the fields without checking their constraints.
+/// A field renamed with `#[serde(rename = "...")]` is matched to the parameter with the new name,
+/// `#[serde(rename_all = "...")]` is not supported.
+pub fn typed_path(input: TokenStream) -> TokenStream {
+    typed_path::typed_path(input)
+}
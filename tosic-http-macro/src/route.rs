@@ -0,0 +1,285 @@
+use regex::Regex;
+use std::ops::Range;
+use syn::LitStr;
+
+/// A segment of a route pattern
+pub(crate) enum Segment {
+    /// Literal text or a segment with parameters, like `users`, `{id}` or `{name}.{ext}`
+    Params(Vec<String>),
+    /// `*`
+    Wildcard,
+    /// `**`, its value is stored under `wildcard_deep`
+    WildcardDeep,
+}
+
+/// An invalid route pattern, `range` is the byte range of the problem in the pattern
+pub(crate) struct RouteError {
+    message: String,
+    range: Range<usize>,
+}
+
+impl RouteError {
+    fn new(message: impl Into<String>, range: Range<usize>) -> Self {
+        Self {
+            message: message.into(),
+            range,
+        }
+    }
+
+    /// Turns the error into a compile error pointing at the invalid part of `pattern`.
+    ///
+    /// Falls back to the whole literal if the compiler can't point into it, or if the literal has escapes
+    /// or is a raw string, where the offsets in the value are not the offsets in the source.
+    pub(crate) fn into_syn(self, pattern: &LitStr) -> syn::Error {
+        let token = pattern.token();
+        let exact = token.to_string() == format!("\"{}\"", pattern.value());
+        let span = exact
+            .then(|| token.subspan(self.range.start + 1..self.range.end + 1))
+            .flatten()
+            .unwrap_or_else(|| pattern.span());
+
+        syn::Error::new(span, self.message)
+    }
+}
+
+/// Parses and validates a route pattern into its segments
+pub(crate) fn parse(pattern: &str) -> Result<Vec<Segment>, RouteError> {
+    let segments = split(pattern);
+    let mut names: Vec<&str> = Vec::new();
+    let mut parsed = Vec::with_capacity(segments.len());
+
+    for (index, &(offset, segment)) in segments.iter().enumerate() {
+        let is_last = index == segments.len() - 1;
+        let range = offset..offset + segment.len();
+
+        let segment = match segment {
+            "*" => Segment::Wildcard,
+            "**" if !is_last => {
+                return Err(RouteError::new(
+                    "`**` matches the rest of the path, it has to be the last segment",
+                    range,
+                ))
+            }
+            "**" => {
+                add_name(&mut names, "wildcard_deep", range)?;
+                Segment::WildcardDeep
+            }
+            segment => {
+                let params = params(segment, offset, is_last)?;
+
+                for (name, range) in &params {
+                    add_name(&mut names, name, range.clone())?;
+                }
+
+                Segment::Params(params.iter().map(|(name, _)| name.to_string()).collect())
+            }
+        };
+
+        parsed.push(segment);
+    }
+
+    Ok(parsed)
+}
+
+/// Joins the prefix of a controller and the pattern of one of its routes, like a scope joins paths.
+///
+/// The joined pattern is validated, errors point into the literal of the prefix or of the route.
+pub(crate) fn join(prefix: &LitStr, pattern: &LitStr) -> syn::Result<String> {
+    let (prefix_value, pattern_value) = (prefix.value(), pattern.value());
+    let prefix_part = prefix_value.trim_end_matches('/');
+    let pattern_part = pattern_value.trim_start_matches('/');
+    let joined = format!("{}/{}", prefix_part, pattern_part);
+
+    if let Err(RouteError { message, range }) = parse(&joined) {
+        let start = prefix_part.len() + 1;
+
+        return Err(if range.start < start {
+            RouteError::new(message, range.start..range.end.min(prefix_part.len())).into_syn(prefix)
+        } else {
+            let offset = pattern_value.len() - pattern_part.len();
+
+            RouteError::new(
+                message,
+                range.start - start + offset..range.end - start + offset,
+            )
+            .into_syn(pattern)
+        });
+    }
+
+    Ok(joined)
+}
+
+/// Returns the names of the parameters of `pattern` in the order they appear
+pub(crate) fn param_names(pattern: &str) -> Result<Vec<String>, RouteError> {
+    let mut names = Vec::new();
+
+    for (segment, (offset, text)) in parse(pattern)?.into_iter().zip(split(pattern)) {
+        match segment {
+            Segment::Params(params) => names.extend(params),
+            Segment::WildcardDeep => names.push("wildcard_deep".to_string()),
+            Segment::Wildcard => {
+                return Err(RouteError::new(
+                    "a wildcard (`*`) has no name, use a parameter like `{name}` instead",
+                    offset..offset + text.len(),
+                ))
+            }
+        }
+    }
+
+    Ok(names)
+}
+
+/// Splits `pattern` into its non-empty segments and their byte offsets
+fn split(pattern: &str) -> Vec<(usize, &str)> {
+    let mut offset = 0;
+
+    pattern
+        .split('/')
+        .filter_map(|segment| {
+            let start = offset;
+            offset += segment.len() + 1;
+
+            (!segment.is_empty()).then_some((start, segment))
+        })
+        .collect()
+}
+
+/// Records the parameter `name`, a route can't have two parameters with the same name
+fn add_name<'a>(
+    names: &mut Vec<&'a str>,
+    name: &'a str,
+    range: Range<usize>,
+) -> Result<(), RouteError> {
+    if names.contains(&name) {
+        return Err(RouteError::new(
+            format!("duplicate parameter `{}`", name),
+            range,
+        ));
+    }
+
+    names.push(name);
+    Ok(())
+}
+
+/// Validates the parameters in a single segment starting at `offset` and returns their names and ranges
+fn params(
+    segment: &str,
+    offset: usize,
+    is_last: bool,
+) -> Result<Vec<(&str, Range<usize>)>, RouteError> {
+    let mut params = Vec::new();
+    let mut index = 0;
+
+    while let Some(next) = segment[index..].find(['{', '}']) {
+        let start = index + next;
+
+        if segment[start..].starts_with('}') {
+            return Err(RouteError::new(
+                "unmatched `}`",
+                offset + start..offset + start + 1,
+            ));
+        }
+
+        let end = start
+            + closing_brace(&segment[start..]).ok_or_else(|| {
+                RouteError::new(
+                    "unclosed `{`, parameters look like `{name}`",
+                    offset + start..offset + segment.len(),
+                )
+            })?;
+        let range = offset + start..offset + end + 1;
+        let parameter = &segment[start + 1..end];
+
+        if let Some(name) = parameter.strip_prefix('*') {
+            if start != 0 || end != segment.len() - 1 {
+                return Err(RouteError::new(
+                    "a catch-all has to be the whole segment",
+                    range,
+                ));
+            }
+
+            if !is_last {
+                return Err(RouteError::new(
+                    "a catch-all matches the rest of the path, it has to be the last segment",
+                    range,
+                ));
+            }
+
+            if name.contains(':') {
+                return Err(RouteError::new(
+                    "a catch-all can't have a constraint",
+                    range,
+                ));
+            }
+
+            if name.is_empty() {
+                return Err(RouteError::new("parameter without a name", range));
+            }
+
+            params.push((name, range));
+            index = end + 1;
+            continue;
+        }
+
+        let (name, constraint) = match parameter.split_once(':') {
+            Some((name, constraint)) => (name, Some(constraint)),
+            None => (parameter, None),
+        };
+
+        if name.is_empty() {
+            return Err(RouteError::new("parameter without a name", range));
+        }
+
+        if let Some(constraint) = constraint {
+            validate_constraint(constraint)
+                .map_err(|error| RouteError::new(error, range.clone()))?;
+        }
+
+        params.push((name, range));
+        index = end + 1;
+    }
+
+    Ok(params)
+}
+
+/// Checks that `constraint` is `int`, `uuid` or a valid regex, like the server does when the route is registered
+fn validate_constraint(constraint: &str) -> Result<(), String> {
+    match constraint {
+        "" => Err("expected a constraint after `:`".to_string()),
+        "int" | "uuid" => Ok(()),
+        pattern => Regex::new(&format!("^(?:{})$", pattern))
+            .map(|_| ())
+            .map_err(|error| {
+                // regex errors draw the position over several lines, only the last one says what is wrong
+                let error = error.to_string();
+                let reason = error.lines().last().unwrap_or_default();
+
+                format!(
+                    "invalid route constraint `{}`: {}",
+                    pattern,
+                    reason.trim_start_matches("error: ")
+                )
+            }),
+    }
+}
+
+/// Finds the index of the brace closing the one `segment` starts with
+fn closing_brace(segment: &str) -> Option<usize> {
+    let mut depth = 0;
+
+    for (index, char) in segment.char_indices() {
+        match char {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
@@ -1,27 +1,146 @@
+use crate::route::{self, Segment};
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, TokenStream as TokenStream2};
-use quote::quote;
-use syn::{parse_macro_input, FnArg, ItemFn, PatType, ReturnType};
+use quote::{quote, quote_spanned};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Expr, FnArg, GenericArgument, ItemFn, Lit, LitStr, PatType, PathArguments,
+    ReturnType, Token, Type,
+};
 
+/// The path of a service, either the pattern or a type implementing `TypedPath`
+enum ServicePath {
+    Pattern(LitStr),
+    Typed(syn::Path),
+}
+
+impl Parse for ServicePath {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            input.parse().map(ServicePath::Pattern)
+        } else {
+            input.parse().map(ServicePath::Typed)
+        }
+    }
+}
+
+/// The HTTP methods a service can be registered for, named like the constants on `Method`
+const METHODS: &[&str] = &[
+    "GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS", "TRACE", "CONNECT",
+];
+
+/// The arguments of a service attribute, `"/path", method = "GET", name = "route", layer = SomeLayer::new(), ...`
+struct ServiceArgs {
+    path: ServicePath,
+    methods: Vec<LitStr>,
+    name: Option<LitStr>,
+    layers: Vec<Expr>,
+}
+
+impl Parse for ServiceArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let mut methods = Vec::new();
+        let mut name = None;
+        let mut layers = Vec::new();
+
+        if input.is_empty() {
+            return Ok(Self {
+                path,
+                methods,
+                name,
+                layers,
+            });
+        }
+
+        input.parse::<Token![,]>()?;
+
+        let options = Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated(input)?;
+
+        for option in options {
+            if option.path.is_ident("layer") {
+                layers.push(option.value);
+            } else if option.path.is_ident("method") {
+                match option.value {
+                    Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(value),
+                        ..
+                    }) => methods.push(value),
+                    value => {
+                        return Err(syn::Error::new_spanned(value, "expected a string literal"))
+                    }
+                }
+            } else if option.path.is_ident("name") {
+                if name.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        option.path,
+                        "the route already has a name",
+                    ));
+                }
+
+                match option.value {
+                    Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(value),
+                        ..
+                    }) => name = Some(value),
+                    value => {
+                        return Err(syn::Error::new_spanned(value, "expected a string literal"))
+                    }
+                }
+            } else {
+                return Err(syn::Error::new_spanned(
+                    option.path,
+                    "unknown option, expected `method`, `name` or `layer`",
+                ));
+            }
+        }
+
+        Ok(Self {
+            path,
+            methods,
+            name,
+            layers,
+        })
+    }
+}
+
+/// Generates the service for `method`, or for every `method = "..."` in the arguments if there is none
 pub(crate) fn service(
     args: TokenStream,
     input: TokenStream,
-    method: &str,
+    method: Option<&str>,
 ) -> proc_macro::TokenStream {
+    let ServiceArgs {
+        path,
+        methods,
+        name,
+        layers,
+    } = parse_macro_input!(args as ServiceArgs);
     let input = parse_macro_input!(input as ItemFn);
 
+    let methods = match service_methods(method, &methods) {
+        Ok(methods) => methods,
+        Err(error) => return error.to_compile_error().into(),
+    };
+
     if input.sig.asyncness.is_none() {
         return syn::Error::new_spanned(input.sig.fn_token, "async fn is required")
             .to_compile_error()
             .into();
     }
 
+    let param_count = match &path {
+        ServicePath::Pattern(pattern) => match route::parse(&pattern.value()) {
+            Ok(segments) => Some(param_count(&segments)),
+            Err(error) => return error.into_syn(pattern).to_compile_error().into(),
+        },
+        ServicePath::Typed(_) => None,
+    };
+
     let crate_name = Ident::new("tosic_http", proc_macro2::Span::call_site());
     let fn_name = input.sig.ident.clone();
-    let method_ident = Ident::new(
-        method.to_uppercase().as_str(),
-        proc_macro2::Span::call_site(),
-    );
 
     let mut idents = Vec::new();
     let mut types = Vec::new();
@@ -34,7 +153,13 @@ pub(crate) fn service(
         }
     });
 
-    let args: TokenStream2 = args.into();
+    let arity_warnings = match (&path, param_count) {
+        (ServicePath::Pattern(pattern), Some(param_count)) => types
+            .iter()
+            .filter_map(|ty| arity_warning(ty, &pattern.value(), param_count))
+            .collect(),
+        _ => Vec::new(),
+    };
 
     let return_type = match input.sig.output {
         ReturnType::Default => quote!(()),
@@ -50,6 +175,23 @@ pub(crate) fn service(
 
     let vis = &input.vis;
 
+    let methods = methods
+        .iter()
+        .map(|method| quote!(#crate_name::prelude::Method::#method));
+
+    let path = match path {
+        ServicePath::Pattern(path) => quote!(#path),
+        ServicePath::Typed(path) => quote!(<#path as #crate_name::traits::TypedPath>::PATH),
+    };
+
+    let name = match name {
+        Some(name) => quote!(Some(#name)),
+        None => quote!(None),
+    };
+
+    // the first layer listed should see the request first so it has to be applied last
+    let layers_outermost_last = layers.iter().rev();
+
     let expanded: TokenStream2 = quote! {
         #[allow(non_camel_case_types)]
         #(#doc_attrs)*
@@ -69,10 +211,153 @@ pub(crate) fn service(
         }
 
         impl #crate_name::services::HttpService<(#(#types,)*)> for #fn_name {
-            const METHOD: #crate_name::prelude::Method = #crate_name::prelude::Method::#method_ident;
-            const PATH: &'static str = #args;
+            const METHODS: &'static [#crate_name::prelude::Method] = &[#(#methods),*];
+            const PATH: &'static str = #path;
+            const NAME: Option<&'static str> = #name;
+
+            #[inline]
+            fn layer(handler: #crate_name::services::HandlerFn) -> #crate_name::services::HandlerFn {
+                handler #(.layer(#layers_outermost_last))*
+            }
         }
+
+        #(#arity_warnings)*
     };
 
     proc_macro::TokenStream::from(expanded)
 }
+
+/// Returns the idents of the `Method` constants the service is registered for.
+///
+/// The method macros like `#[get]` give the method themselves, `#[route]` needs at least one `method = "..."`.
+fn service_methods(method: Option<&str>, methods: &[LitStr]) -> syn::Result<Vec<Ident>> {
+    if let Some(method) = method {
+        if let Some(extra) = methods.first() {
+            return Err(syn::Error::new_spanned(
+                extra,
+                format!(
+                    "the method is already `{}`, use `#[route]` for several methods",
+                    method.to_uppercase()
+                ),
+            ));
+        }
+
+        return Ok(vec![Ident::new(
+            &method.to_uppercase(),
+            proc_macro2::Span::call_site(),
+        )]);
+    }
+
+    if methods.is_empty() {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "expected at least one method, like `#[route(\"/path\", method = \"GET\")]`",
+        ));
+    }
+
+    let mut idents: Vec<Ident> = Vec::with_capacity(methods.len());
+
+    for method in methods {
+        let name = method.value().to_uppercase();
+
+        if !METHODS.contains(&name.as_str()) {
+            return Err(syn::Error::new_spanned(
+                method,
+                format!("unknown method, expected one of {}", METHODS.join(", ")),
+            ));
+        }
+
+        if idents.iter().any(|ident| *ident == name) {
+            return Err(syn::Error::new_spanned(
+                method,
+                format!("`{}` is already listed", name),
+            ));
+        }
+
+        idents.push(Ident::new(&name, method.span()));
+    }
+
+    Ok(idents)
+}
+
+/// Counts the parameters a request matching the route has, an unnamed `*` captures nothing
+fn param_count(segments: &[Segment]) -> usize {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Params(params) => params.len(),
+            Segment::WildcardDeep => 1,
+            Segment::Wildcard => 0,
+        })
+        .sum()
+}
+
+/// Returns how many parameters a `Path<...>` argument extracts, if it can be told from the type alone.
+///
+/// Tuples and arrays extract one parameter per element and primitives extract exactly one, structs and
+/// maps extract by name so any number of parameters can fit them.
+fn path_arity(ty: &Type) -> Option<usize> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+
+    if segment.ident != "Path" {
+        return None;
+    }
+
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    let Some(GenericArgument::Type(inner)) = arguments.args.first() else {
+        return None;
+    };
+
+    match inner {
+        Type::Tuple(tuple) if !tuple.elems.is_empty() => Some(tuple.elems.len()),
+        Type::Array(array) => match &array.len {
+            Expr::Lit(syn::ExprLit {
+                lit: Lit::Int(len), ..
+            }) => len.base10_parse().ok(),
+            _ => None,
+        },
+        Type::Path(path) if path.qself.is_none() => {
+            const PRIMITIVES: &[&str] = &[
+                "bool", "char", "String", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16",
+                "i32", "i64", "i128", "isize", "f32", "f64",
+            ];
+
+            path.path
+                .get_ident()
+                .filter(|ident| PRIMITIVES.iter().any(|primitive| ident == primitive))
+                .map(|_| 1)
+        }
+        _ => None,
+    }
+}
+
+/// Warns if the `Path<...>` argument `ty` can't extract the parameters of `pattern`.
+///
+/// Proc macros can't emit warnings on stable, so this uses a deprecated item pointing at the argument.
+fn arity_warning(ty: &Type, pattern: &str, param_count: usize) -> Option<TokenStream2> {
+    let arity = path_arity(ty)?;
+
+    if arity == param_count {
+        return None;
+    }
+
+    let note = format!(
+        "`Path` extracts {} parameter(s) but the route `{}` has {}, so the extraction always fails",
+        arity, pattern, param_count
+    );
+
+    Some(quote_spanned! {ty.span()=>
+        const _: () = {
+            #[deprecated(note = #note)]
+            #[allow(non_camel_case_types)]
+            struct path_arity_mismatch;
+
+            let _ = path_arity_mismatch;
+        };
+    })
+}
@@ -0,0 +1,169 @@
+use crate::route::param_names;
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span};
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Expr, ExprLit, Field, Fields, Lit, LitStr,
+    Meta, Token,
+};
+
+pub(crate) fn typed_path(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(input) {
+        Ok(expanded) => expanded.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let crate_name = Ident::new("tosic_http", Span::call_site());
+    let ident = &input.ident;
+
+    let attr = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("typed_path"))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                ident,
+                "missing the route pattern, add `#[typed_path(\"/path/{param}\")]`",
+            )
+        })?;
+    let path: LitStr = attr.parse_args()?;
+
+    let names = param_names(&path.value()).map_err(|error| error.into_syn(&path))?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect(),
+            Fields::Unit => Vec::new(),
+            Fields::Unnamed(fields) => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "expected named fields matching the parameters of the route",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "`TypedPath` can only be derived for structs",
+            ))
+        }
+    };
+
+    if let Some(attr) = serde_attribute(&input.attrs, "rename_all")? {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "`rename_all` is not supported, rename the fields with `#[serde(rename = \"...\")]` instead",
+        ));
+    }
+
+    let mut field_names = Vec::new();
+    let mut param_names = Vec::new();
+
+    for field in &fields {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let param = deserialized_name(field)?.unwrap_or_else(|| ident.to_string());
+
+        if !names.contains(&param) {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("the route `{}` has no parameter `{}`", path.value(), param),
+            ));
+        }
+
+        field_names.push(ident);
+        param_names.push(param);
+    }
+
+    if let Some(name) = names.iter().find(|name| !param_names.contains(name)) {
+        return Err(syn::Error::new_spanned(
+            &path,
+            format!("the parameter `{}` has no field", name),
+        ));
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #crate_name::traits::TypedPath for #ident #ty_generics #where_clause {
+            const PATH: &'static str = #path;
+
+            fn params(&self) -> Vec<(&'static str, String)> {
+                vec![#((#param_names, ::std::string::ToString::to_string(&self.#field_names))),*]
+            }
+        }
+
+        impl #impl_generics #crate_name::traits::FromRequest for #ident #ty_generics #where_clause {
+            type Error = #crate_name::extractors::ExtractionError;
+            type Future = ::std::future::Ready<Result<Self, Self::Error>>;
+
+            #[inline]
+            fn from_request(
+                req: &#crate_name::request::HttpRequest,
+                _: &mut #crate_name::request::HttpPayload,
+            ) -> Self::Future {
+                ::std::future::ready(<Self as #crate_name::traits::TypedPath>::from_params(req.params()))
+            }
+        }
+
+        impl #impl_generics ::std::fmt::Display for #ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                let url = <Self as #crate_name::traits::TypedPath>::to_url_unchecked(self)
+                    .map_err(|_| ::std::fmt::Error)?;
+                f.write_str(&url)
+            }
+        }
+    })
+}
+
+/// Returns the name `Deserialize` expects for `field`, set with `#[serde(rename = "...")]`
+/// or `#[serde(rename(deserialize = "..."))]`
+fn deserialized_name(field: &Field) -> syn::Result<Option<String>> {
+    let value = match serde_attribute(&field.attrs, "rename")? {
+        Some(Meta::NameValue(rename)) => rename.value,
+        Some(Meta::List(list)) => {
+            let renames = list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+
+            match renames.into_iter().find_map(|meta| match meta {
+                Meta::NameValue(rename) if rename.path.is_ident("deserialize") => {
+                    Some(rename.value)
+                }
+                _ => None,
+            }) {
+                Some(value) => value,
+                None => return Ok(None),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(name),
+            ..
+        }) => Ok(Some(name.value())),
+        value => Err(syn::Error::new_spanned(value, "expected a string literal")),
+    }
+}
+
+/// Finds the `serde` option `name` in `attrs`, like `rename` in `#[serde(rename = "id")]`
+fn serde_attribute(attrs: &[Attribute], name: &str) -> syn::Result<Option<Meta>> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        let options = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+
+        if let Some(option) = options
+            .into_iter()
+            .find(|option| option.path().is_ident(name))
+        {
+            return Ok(Some(option));
+        }
+    }
+
+    Ok(None)
+}